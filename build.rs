@@ -0,0 +1,34 @@
+use std::env;
+use std::path::PathBuf;
+
+// Header shipped along with the C library, checked in and compared to the generated one by tests/ffi.rs
+const HEADER: &str = "include/pascaline.h";
+const HEADER_NAME: &str = "pascaline.h";
+
+// Set to overwrite the checked-in header, after changing the C API
+const UPDATE_HEADER: &str = "PASCALINE_UPDATE_HEADER";
+
+fn main() {
+    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed={}", UPDATE_HEADER);
+
+    // The header is only needed by C users, so failing to generate it must not break the build
+    let bindings = match cbindgen::generate(&root) {
+        Ok(b) => b,
+        Err(e) => {
+            println!("cargo:warning=Failed to generate the C header : {}", e);
+            return;
+        }
+    };
+
+    // Builds only write to their own directory, the source tree is left alone unless asked
+    bindings.write_to_file(out.join(HEADER_NAME));
+
+    if env::var_os(UPDATE_HEADER).is_some() {
+        bindings.write_to_file(root.join(HEADER));
+    }
+}
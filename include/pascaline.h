@@ -0,0 +1,129 @@
+#ifndef PASCALINE_H
+#define PASCALINE_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Type of a value held by the stack
+ */
+typedef enum PascalineType {
+  PASCALINE_TYPE_INTEGER,
+  PASCALINE_TYPE_FLOAT,
+  PASCALINE_TYPE_BOOL,
+  PASCALINE_TYPE_QUANTITY,
+} PascalineType;
+
+/**
+ * Calculator handle, along with the last error and messages raised through it
+ */
+typedef struct PascalineCalculator PascalineCalculator;
+
+/**
+ * Content of a value, to be read according to its type
+ *
+ * Quantities are read as their magnitude, in their own unit, which has no C counterpart.
+ */
+typedef union PascalineData {
+  int32_t integer;
+  float floating;
+  bool boolean;
+} PascalineData;
+
+/**
+ * Value held by the stack, tagged with its type
+ */
+typedef struct PascalineValue {
+  enum PascalineType kind;
+  union PascalineData data;
+} PascalineValue;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a calculator, to be freed with `pascaline_free`
+ */
+struct PascalineCalculator *pascaline_new(void);
+
+/**
+ * Frees a calculator created with `pascaline_new`
+ *
+ * # Safety
+ *
+ * `calculator` must come from `pascaline_new` and must not be used afterwards.
+ */
+void pascaline_free(struct PascalineCalculator *calculator);
+
+/**
+ * Evaluates a nul-terminated UTF-8 text, returning 0 on success or the code of the error
+ *
+ * # Safety
+ *
+ * `calculator` must come from `pascaline_new`, and `text` must be nul-terminated.
+ */
+uint16_t pascaline_eval(struct PascalineCalculator *calculator, const char *text);
+
+/**
+ * Number of values in the stack
+ *
+ * # Safety
+ *
+ * `calculator` must come from `pascaline_new`.
+ */
+size_t pascaline_depth(const struct PascalineCalculator *calculator);
+
+/**
+ * Reads the value at `index` (0 being the deepest), returning false if there is none
+ *
+ * # Safety
+ *
+ * `calculator` must come from `pascaline_new`, and `value` must point to a writable value.
+ */
+bool pascaline_get(const struct PascalineCalculator *calculator,
+                   size_t index,
+                   struct PascalineValue *value);
+
+/**
+ * Code of the last error raised by `pascaline_eval`, or 0 if it succeeded
+ *
+ * # Safety
+ *
+ * `calculator` must come from `pascaline_new`.
+ */
+uint16_t pascaline_error_code(const struct PascalineCalculator *calculator);
+
+/**
+ * Message of the last error raised by `pascaline_eval`, or null if it succeeded
+ *
+ * The message is owned by the calculator, and lives until the next call to `pascaline_eval`.
+ *
+ * # Safety
+ *
+ * `calculator` must come from `pascaline_new`.
+ */
+const char *pascaline_error_message(const struct PascalineCalculator *calculator);
+
+/**
+ * Messages written by commands such as `HELP` during the last call to `pascaline_eval`, one per line,
+ * or null if there are none
+ *
+ * The messages are owned by the calculator, and live until the next call to `pascaline_eval`.
+ *
+ * # Safety
+ *
+ * `calculator` must come from `pascaline_new`.
+ */
+const char *pascaline_output(const struct PascalineCalculator *calculator);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* PASCALINE_H */
@@ -1,6 +1,853 @@
+use crate::core::{ Category, Command, Constant, Metadata, OperatorImpl, Parser, Registry, Span, Stack, Token };
+use crate::errors::{ EvalError, PascalineError };
+use crate::session::{ write_atomically, Session };
+use std::fs;
+use std::path::Path;
+use std::time::{ Duration, Instant };
+
+/// Bounds on a single evaluation, so untrusted texts cannot keep a calculator busy forever
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Limits {
+    /// Maximum number of words evaluated at once
+    pub max_steps: Option<usize>,
+    /// Maximum duration of an evaluation
+    pub timeout: Option<Duration>
+}
+
+/// Evaluates texts, gluing the parser, the registry of operators and the computation stack
 #[derive(Debug)]
-pub struct Calculator;
+pub struct Calculator {
+    registry: Registry,
+    parser: Parser,
+    stack: Stack,
+    atomic: bool,
+    limits: Limits,
+    sandboxed: bool,
+    strict: bool,
+    output: Vec<String>
+}
 
 impl Calculator {
+    /// Creates a calculator with an empty stack and all the built-in operators
+    pub fn new() -> Calculator {
+        Calculator {
+            registry: Registry::new(),
+            parser: Parser,
+            stack: Stack::new(),
+            atomic: false,
+            limits: Limits::default(),
+            sandboxed: false,
+            strict: false,
+            output: Vec::new()
+        }
+    }
+
+    /// Operators known by the calculator
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Registers a custom operator, failing if its symbol is invalid or already in use
+    pub fn register(&mut self, op: &'static dyn OperatorImpl) -> Result<(), PascalineError> {
+        // Custom operators can be used right away, just like built-in ones
+        self.registry.register(op).map(|_| ())
+    }
+
+    /// Gives another name to an operator, failing if either is invalid or the name already in use
+    pub fn alias(&mut self, alias: &str, symbol: &str) -> Result<(), PascalineError> {
+        self.registry.alias(alias, symbol).map(|_| ())
+    }
+
+    /// Whether a failing evaluation leaves the state untouched
+    pub fn is_atomic(&self) -> bool {
+        self.atomic
+    }
+
+    /// Enables or disables atomic evaluation
+    pub fn set_atomic(&mut self, atomic: bool) {
+        // When atomic, a line either applies completely or not at all
+        self.atomic = atomic;
+    }
+
+    /// Whether invalid operations and overflows give NaN and infinite values instead of errors
+    pub fn is_ieee(&self) -> bool {
+        self.stack.is_ieee()
+    }
+
+    /// Enables or disables IEEE 754 semantics
+    pub fn set_ieee(&mut self, ieee: bool) {
+        self.stack.set_ieee(ieee);
+    }
+
+    /// Whether trigonometric functions take angles in degrees, and their inverses give them, instead of radians
+    pub fn is_degrees(&self) -> bool {
+        self.stack.is_degrees()
+    }
+
+    /// Switches angles to degrees, or back to radians
+    pub fn set_degrees(&mut self, degrees: bool) {
+        self.stack.set_degrees(degrees);
+    }
+
+    /// Number of values the stack can hold, or `None` if unbounded
+    pub fn capacity(&self) -> Option<usize> {
+        self.stack.capacity()
+    }
+
+    /// Changes the number of values the stack can hold, `None` meaning unbounded
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.stack.set_capacity(capacity);
+    }
+
+    /// Bounds applied to each evaluation
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Sets the bounds applied to each evaluation
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Whether commands touching the file system are refused
+    pub fn is_sandboxed(&self) -> bool {
+        self.sandboxed
+    }
+
+    /// Enables or disables commands touching the file system, for untrusted texts
+    pub fn set_sandboxed(&mut self, sandboxed: bool) {
+        self.sandboxed = sandboxed;
+    }
+
+    /// Whether unknown words are errors instead of being ignored
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Enables or disables errors on unknown words
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Takes the messages written by commands such as `HELP`, for the frontend to show them
+    pub fn take_output(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Computation stack of the calculator
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    /// Computation stack of the calculator, to edit values in place
+    pub fn stack_mut(&mut self) -> &mut Stack {
+        &mut self.stack
+    }
+
+    /// Pushes a single token, applying it right away if it is an operator
+    pub fn push(&mut self, token: Token) -> Result<(), PascalineError> {
+        self.stack.push(token)
+    }
+
+    /// Removes the value on top of the stack and returns it, if any
+    pub fn pop(&mut self) -> Option<Token> {
+        self.stack.pop()
+    }
+
+    /// Empties the stack and forgets the last operator, keeping the modes as they are
+    pub fn reset(&mut self) {
+        self.stack.restore(Vec::new(), None);
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn set_stack(&mut self, stack: Stack) {
+        // Modes and capacity belong to the calculator, not to the values being put back
+        let ieee = self.stack.is_ieee();
+        let degrees = self.stack.is_degrees();
+        let capacity = self.stack.capacity();
+
+        self.stack = stack;
+        self.stack.set_ieee(ieee);
+        self.stack.set_degrees(degrees);
+        self.stack.set_capacity(capacity);
+    }
+
+    /// Parses and evaluates a text, token by token
+    pub fn eval(&mut self, text: &str) -> Result<(), EvalError> {
+        if self.atomic {
+            self.transaction(|c| c.eval_tokens(text))
+        } else {
+            self.eval_tokens(text)
+        }
+    }
+
+    fn eval_tokens(&mut self, text: &str) -> Result<(), EvalError> {
+        // Tokens are pushed one by one, stopping at the first error
+        let mut words = self.parser.tokenize_with_spans(text).into_iter();
+        let start = Instant::now();
+        let mut steps = 0;
+
+        while let Some((mut span, word)) = words.next() {
+            steps += 1;
+
+            let result = self.check_limits(steps, start).and_then(|_| match Command::from_symbol(word) {
+                // Arguments are words of their own, counted as steps, and errors point at the whole command
+                Some(command) if command.takes_argument() => match words.next() {
+                    Some((argument_span, argument)) => {
+                        steps += 1;
+                        span = Span { start: span.start, end: argument_span.end };
+
+                        self.check_limits(steps, start)
+                            .and_then(|_| self.run(command, Some(self.parser.unquote(argument))))
+                    },
+                    None => Err(PascalineError::ArgumentError(word.to_string()))
+                },
+                Some(command) => self.run(command, None),
+                None => {
+                    let token = self.parser.make_token(word, &self.registry);
+
+                    // Unknown words are ignored, unless strict, but a number with an unknown unit is always a mistake
+                    if token.is_legit() {
+                        self.stack.push(token)
+                    } else if let Some(unit) = self.parser.unknown_unit(word) {
+                        Err(PascalineError::UnitError(unit.to_string()))
+                    } else if let Some(f) = self.parser.overflowing_literal(word) {
+                        // Too large literals are only infinite in IEEE mode, like any other overflow
+                        if self.is_ieee() {
+                            self.stack.push(Token::Float(f))
+                        } else {
+                            Err(PascalineError::OverflowError { op: word.to_string() })
+                        }
+                    } else if self.strict {
+                        Err(PascalineError::UnknownWordError(word.to_string()))
+                    } else {
+                        Ok(())
+                    }
+                }
+            });
+
+            if let Err(error) = result {
+                return Err(EvalError {
+                    error,
+                    span,
+                    token: text[span.start..span.end].to_string()
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run(&mut self, command: Command, argument: Option<&str>) -> Result<(), PascalineError> {
+        // Only called with an argument for the commands taking one
+        let argument = argument.unwrap_or_default();
+
+        match command {
+            Command::Save | Command::Load if self.sandboxed => Err(PascalineError::SandboxError(command.symbol().to_string())),
+            Command::Save => self.save(argument),
+            Command::Load => self.load(argument),
+            Command::Help => self.help(argument).map(|h| self.output.push(h)),
+            Command::Ops => {
+                let listing = self.ops();
+
+                self.output.push(listing);
+                Ok(())
+            },
+            Command::Const => self.constant(argument).and_then(|t| self.stack.push(t)),
+            Command::Consts => {
+                let listing = self.consts();
+
+                self.output.push(listing);
+                Ok(())
+            },
+            Command::Deg | Command::Rad => {
+                self.set_degrees(command == Command::Deg);
+                Ok(())
+            }
+        }
+    }
+
+    fn help(&self, symbol: &str) -> Result<String, PascalineError> {
+        if let Some(c) = Constant::lookup(symbol) {
+            return Ok(format!("{} : {} (constant)\n  {}\n  {} => [{}]", c.symbol, c.description, c, c.symbol, c.token()));
+        }
+
+        let metadata = match Command::from_symbol(symbol) {
+            Some(command) => command.metadata(),
+            None => self.registry.get(symbol)
+                .ok_or_else(|| PascalineError::OperatorSymbolError(symbol.to_string()))?
+                .metadata()
+        };
+        let mut lines = vec![format!("{} : {} ({})", metadata.symbol, metadata.description, metadata.category)];
+
+        if !metadata.effect.is_empty() {
+            lines.push(format!("  {}", metadata.effect));
+        }
+
+        // Examples are run for real, so what is shown is always accurate
+        for example in metadata.examples {
+            let mut calculator = Calculator::new();
+            let result = match calculator.eval(example) {
+                Ok(_) => calculator.stack().to_string(),
+                Err(e) => e.error.to_string()
+            };
+
+            lines.push(format!("  {} => {}", example, result));
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    fn ops(&self) -> String {
+        let metadata = self.registry.operators().iter()
+            .map(|o| o.metadata())
+            .chain(Command::all().map(|c| c.metadata()))
+            .collect::<Vec<Metadata>>();
+
+        Category::ALL.iter()
+            .map(|c| (c, metadata.iter().filter(|m| m.category == *c).map(|m| m.symbol).collect::<Vec<&str>>()))
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(c, symbols)| format!("{} : {}", c, symbols.join(" ")))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn constant(&self, name: &str) -> Result<Token, PascalineError> {
+        // Physical constants can be named without their leading underscore
+        Constant::lookup(name)
+            .or_else(|| Constant::lookup(&format!("_{}", name)))
+            .map(Constant::token)
+            .ok_or_else(|| PascalineError::ConstantError(name.to_string()))
+    }
+
+    fn consts(&self) -> String {
+        Constant::all()
+            .map(|c| {
+                let exact = if c.is_exact() && !c.unit.is_empty() { ", exact" } else { "" };
+
+                format!("{} : {} = {}{}", c.symbol, c.description, c, exact)
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn check_limits(&self, steps: usize, start: Instant) -> Result<(), PascalineError> {
+        match (self.limits.max_steps, self.limits.timeout) {
+            (Some(limit), _) if steps > limit => Err(PascalineError::StepLimitError { limit }),
+            (_, Some(limit)) if start.elapsed() >= limit => Err(PascalineError::TimeLimitError {
+                millis: limit.as_millis()
+            }),
+            _ => Ok(())
+        }
+    }
+
+    /// Runs `f`, rolling the stack and the modes back to their previous state if it fails
+    pub fn transaction<T, E, F>(&mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Calculator) -> Result<T, E>
+    {
+        // Keep a snapshot of the whole state (values, last operator and modes, which LOAD changes)
+        // so we can roll back to it if anything goes wrong
+        let snapshot = (self.stack.clone(), self.atomic);
+        let result = f(self);
+
+        if result.is_err() {
+            self.stack = snapshot.0;
+            self.atomic = snapshot.1;
+        }
+
+        result
+    }
+
+    /// Value on top of the stack, if any
+    pub fn result(&self) -> Option<&Token> {
+        self.stack.result()
+    }
+
+    /// Serializes the whole state (stack, last operator and modes) to a versioned JSON text
+    pub fn dump(&self) -> String {
+        Session {
+            values: self.stack.values().to_vec(),
+            last_op: self.stack.last_op(),
+            atomic: self.atomic,
+            ieee: self.stack.is_ieee(),
+            degrees: self.stack.is_degrees()
+        }.to_json()
+    }
+
+    /// Replaces the whole state with one serialized by [`Calculator::dump`]
+    ///
+    /// Custom operators must have been registered beforehand.
+    pub fn restore(&mut self, text: &str) -> Result<(), PascalineError> {
+        let session = Session::from_json(text, &self.registry)?;
+
+        // A session saved with a larger capacity may not fit
+        self.stack.check_capacity(session.values.len())?;
+        self.stack.restore(session.values, session.last_op);
+        self.stack.set_ieee(session.ieee);
+        self.stack.set_degrees(session.degrees);
+        self.atomic = session.atomic;
+
+        Ok(())
+    }
+
+    /// Saves the whole state to a file, atomically replacing any previous content
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PascalineError> {
+        write_atomically(path.as_ref(), &self.dump()).map_err(|e| PascalineError::IoError {
+            path: path.as_ref().display().to_string(),
+            reason: e.to_string()
+        })
+    }
+
+    /// Loads the whole state from a file written by [`Calculator::save`]
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), PascalineError> {
+        let text = fs::read_to_string(path.as_ref()).map_err(|e| PascalineError::IoError {
+            path: path.as_ref().display().to_string(),
+            reason: e.to_string()
+        })?;
+
+        self.restore(&text)
+    }
+}
+
+impl Default for Calculator {
+    fn default() -> Calculator {
+        Calculator::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calculator::{ Calculator, Limits };
+    use crate::core::{ Operator, OperatorImpl, Parser, Span, Stack, Token };
+    use crate::errors::{ EvalError, PascalineError };
+    use std::env;
+    use std::fs;
+    use std::process;
+    use std::time::Duration;
+
+    #[test]
+    fn test_eval() {
+        let mut c = Calculator::new();
+
+        c.eval("2 3 + 4 *").unwrap();
+
+        assert_eq!(c.stack().size(), 1);
+        assert_eq!(c.result(), Some(&Token::new_integer(20)));
+    }
+
+    #[test]
+    fn test_eval_many_lines() {
+        let mut c = Calculator::new();
+
+        c.eval("2 3").unwrap();
+        c.eval("+ # Comments are ignored").unwrap();
+
+        assert_eq!(c.result(), Some(&Token::new_integer(5)));
+    }
+
+    #[test]
+    fn test_eval_error() {
+        let mut c = Calculator::new();
+
+        match c.eval("1 +") {
+            Err(EvalError { error: PascalineError::ArityError { expected: 2, found: 1, .. }, span, token }) => {
+                assert_eq!(span, Span { start: 2, end: 3 });
+                assert_eq!(token, "+");
+            },
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_eval_last_operator() {
+        let mut c = Calculator::new();
+
+        c.eval("2 3 4 * LASTOP").unwrap();
+
+        assert_eq!(c.result(), Some(&Token::new_integer(24)));
+
+        // Replaying the last operator makes it the last operator again
+        c.eval("5 LASTOP").unwrap();
+
+        assert_eq!(c.result(), Some(&Token::new_integer(120)));
+    }
+
+    #[test]
+    fn test_eval_partial() {
+        let mut c = Calculator::new();
+
+        c.eval("5 6").unwrap();
+
+        assert!(c.eval("+ DROP DROP").is_err());
+        assert_eq!(c.stack().size(), 0);
+    }
+
+    #[test]
+    fn test_eval_atomic() {
+        let mut c = Calculator::new();
+
+        c.set_atomic(true);
+        c.eval("5 6").unwrap();
+
+        assert!(c.eval("+ DROP DROP").is_err());
+        assert_eq!(c.stack().size(), 2);
+        assert_eq!(c.result(), Some(&Token::new_integer(6)));
+
+        // The last operator must be rolled back as well
+        match c.eval("LASTOP") {
+            Err(EvalError { error: PascalineError::NoLastOperatorError, .. }) => (),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut c = Calculator::new();
+
+        c.eval("1 2").unwrap();
+
+        let result: Result<(), ()> = c.transaction(|c| {
+            c.eval("+ 10 *").unwrap();
+            Err(())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(c.stack().size(), 2);
+
+        c.transaction(|c| c.eval("+ 10 *")).unwrap();
+
+        assert_eq!(c.result(), Some(&Token::new_integer(30)));
+    }
+
+    #[derive(Debug)]
+    struct Dist;
+
+    impl OperatorImpl for Dist {
+        fn symbol(&self) -> &'static str {
+            "DIST"
+        }
+
+        fn arity(&self) -> usize {
+            2
+        }
+
+        fn apply(&self, operands: &[Token]) -> Result<Token, PascalineError> {
+            let x = operands[0].as_float().ok_or(PascalineError::TypeError)?;
+            let y = operands[1].as_float().ok_or(PascalineError::TypeError)?;
+
+            Ok(Token::new_float(x.hypot(y)))
+        }
+    }
+
+    static DIST: Dist = Dist;
+
+    #[test]
+    fn test_register() {
+        let mut c = Calculator::new();
+
+        // Unknown symbols are ignored until registered
+        c.eval("3 4 DIST").unwrap();
+
+        assert_eq!(c.stack().size(), 2);
+
+        c.register(&DIST).unwrap();
+        c.eval("DIST").unwrap();
+
+        assert_eq!(c.result(), Some(&Token::new_integer(5)));
+
+        // The last operator works with custom operators as well
+        c.eval("12 LASTOP").unwrap();
+
+        assert_eq!(c.result(), Some(&Token::new_integer(13)));
+
+        match c.register(&DIST) {
+            Err(PascalineError::SymbolCollisionError(s)) => assert_eq!(s, "DIST"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_dump_restore() {
+        let mut c = Calculator::new();
+
+        c.set_ieee(true);
+        c.eval("1 2.5 + TRUE 3").unwrap();
+
+        let mut restored = Calculator::new();
+
+        restored.restore(&c.dump()).unwrap();
+
+        assert!(restored.is_ieee());
+        assert_eq!(restored.stack().values(), c.stack().values());
+
+        // The last operator is restored as well
+        restored.eval("LASTOP").unwrap();
+
+        assert_eq!(restored.result(), Some(&Token::new_integer(4)));
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut c = Calculator::new();
+
+        c.eval("1 2 3").unwrap();
+
+        let dump = c.dump();
+
+        c.set_capacity(Some(2));
+
+        // Values already there are kept, but neither pushed nor restored beyond the capacity
+        match c.eval("4") {
+            Err(EvalError { error: PascalineError::FullStackError { capacity: 2 }, .. }) => (),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match c.restore(&dump) {
+            Err(PascalineError::FullStackError { capacity: 2 }) => (),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        c.eval("+ +").unwrap();
+
+        assert_eq!(c.result(), Some(&Token::new_integer(6)));
+    }
+
+    #[test]
+    fn test_save_load_commands() {
+        let path = env::temp_dir().join(format!("pascaline-test-{}.json", process::id()));
+        let mut c = Calculator::new();
+
+        c.eval(&format!("6 7 * SAVE {} CLEAR", path.display())).unwrap();
+
+        assert_eq!(c.stack().size(), 0);
+
+        c.eval(&format!("LOAD {} 2 /", path.display())).unwrap();
+
+        assert_eq!(c.result(), Some(&Token::new_integer(21)));
+
+        fs::remove_file(&path).unwrap();
+
+        match c.eval("LOAD") {
+            Err(EvalError { error: PascalineError::ArgumentError(s), .. }) => assert_eq!(s, "LOAD"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        // Errors point at the command along with its argument
+        match c.eval(&format!("1 LOAD {}", path.display())) {
+            Err(EvalError { error: PascalineError::IoError { .. }, token, span }) => {
+                assert_eq!(token, format!("LOAD {}", path.display()));
+                assert_eq!(span.start, 2);
+            },
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        // Paths with spaces are quoted
+        let spaced = env::temp_dir().join(format!("pascaline test {}.json", process::id()));
+
+        c.eval(&format!("CLEAR 5 SAVE \"{}\" # Saved", spaced.display())).unwrap();
+        c.eval(&format!("CLEAR LOAD \"{}\"", spaced.display())).unwrap();
+
+        assert_eq!(c.result(), Some(&Token::new_integer(5)));
+
+        fs::remove_file(&spaced).unwrap();
+    }
+
+    #[test]
+    fn test_load_rollback() {
+        let path = env::temp_dir().join(format!("pascaline-rollback-{}.json", process::id()));
+        let mut c = Calculator::new();
+
+        c.save(&path).unwrap();
+        c.set_atomic(true);
+
+        // Loading changes the modes, which are rolled back along with the stack
+        assert!(c.eval(&format!("1 LOAD {} +", path.display())).is_err());
+        assert!(c.is_atomic());
+        assert_eq!(c.stack().size(), 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+        assert_send_sync::<Calculator>();
+        assert_send_sync::<Parser>();
+        assert_send_sync::<Stack>();
+        assert_send_sync::<Token>();
+        assert_send_sync::<Operator>();
+        assert_send_sync::<EvalError>();
+    }
+
+    #[test]
+    fn test_stack_outlives_input() {
+        let mut c = Calculator::new();
+
+        c.eval("0").unwrap();
+
+        for i in 1..=3 {
+            // Each line is dropped right after being evaluated
+            let line = format!("{} +", i);
+
+            c.eval(&line).unwrap();
+        }
+
+        assert_eq!(c.result(), Some(&Token::new_integer(6)));
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut c = Calculator::new();
+
+        c.set_limits(Limits { max_steps: Some(3), timeout: None });
+        c.eval("1 2 +").unwrap();
+
+        match c.eval("1 2 + 3") {
+            Err(EvalError { error: PascalineError::StepLimitError { limit: 3 }, token, .. }) => assert_eq!(token, "3"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        // Arguments of commands are steps as well
+        match c.eval("1 2 CONST c") {
+            Err(EvalError { error: PascalineError::StepLimitError { limit: 3 }, token, .. }) => assert_eq!(token, "CONST c"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        c.set_limits(Limits { max_steps: None, timeout: Some(Duration::from_secs(0)) });
+
+        match c.eval("1") {
+            Err(EvalError { error: PascalineError::TimeLimitError { millis: 0 }, .. }) => (),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_sandboxed() {
+        let mut c = Calculator::new();
+
+        c.set_sandboxed(true);
+
+        match c.eval("1 SAVE /tmp/nope.json") {
+            Err(EvalError { error: PascalineError::SandboxError(s), .. }) => assert_eq!(s, "SAVE"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_strict() {
+        let mut c = Calculator::new();
+
+        c.eval("1 nope 2 # Comments are not words").unwrap();
+        c.set_strict(true);
+
+        match c.eval("3 nope 4") {
+            Err(EvalError { error: PascalineError::UnknownWordError(w), token, .. }) => {
+                assert_eq!(w, "nope");
+                assert_eq!(token, "nope");
+            },
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        assert_eq!(c.stack().values(), &[Token::Integer(1), Token::Integer(2), Token::Integer(3)]);
+    }
+
+    #[test]
+    fn test_non_finite_literals() {
+        let mut c = Calculator::new();
+
+        // Words read as floats by Rust are not numbers here
+        c.eval("nan inf INFINITY").unwrap();
+
+        assert_eq!(c.stack().size(), 0);
+
+        match c.eval("1e40") {
+            Err(EvalError { error: PascalineError::OverflowError { op }, .. }) => assert_eq!(op, "1e40"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        c.set_ieee(true);
+        c.eval("-1e40").unwrap();
+
+        assert_eq!(c.stack().values(), &[Token::Float(f32::NEG_INFINITY)]);
+    }
+
+    #[test]
+    fn test_unknown_units() {
+        let mut c = Calculator::new();
+
+        // Temperatures with an offset are not units, so they are reported like any unknown unit
+        for (text, unit) in [("1_foo", "foo"), ("2 20_degC", "degC"), ("3_m/furlong", "m/furlong")] {
+            match c.eval(text) {
+                Err(EvalError { error: PascalineError::UnitError(u), .. }) => assert_eq!(u, unit),
+                r => panic!("Unexpected result : {:?}", r)
+            }
+        }
+
+        assert_eq!(c.stack().values(), &[Token::Integer(2)]);
+    }
+
+    #[test]
+    fn test_help() {
+        let mut c = Calculator::new();
+
+        c.eval("1 HELP + HELP DUP").unwrap();
+
+        let output = c.take_output();
+
+        assert_eq!(output[0], "+ : Adds two numbers (arithmetic)\n  ( x y -- x+y )\n  2 3 + => [5]");
+        assert!(output[1].contains("1 2 DUP => [1, 2, 2]"));
+        assert!(c.take_output().is_empty());
+        assert_eq!(c.stack().size(), 1);
+
+        match c.eval("HELP NOPE") {
+            Err(EvalError { error: PascalineError::OperatorSymbolError(s), .. }) => assert_eq!(s, "NOPE"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        c.eval("HELP SAVE").unwrap();
+
+        assert_eq!(c.take_output(), vec!["SAVE : Saves the whole state to a file, quoting paths with spaces (commands)\n  SAVE file"]);
+
+        c.eval("HELP E").unwrap();
+
+        assert_eq!(c.take_output(), vec!["E : Base of the natural logarithm (constant)\n  2.718281828459045\n  E => [2.718282]"]);
+    }
+
+    #[test]
+    fn test_degrees() {
+        let mut c = Calculator::new();
+
+        c.eval("DEG 90 SIN 1 ARCTAN").unwrap();
+
+        assert!(c.is_degrees());
+        assert_eq!(c.stack().values(), &[Token::Integer(1), Token::Integer(45)]);
+
+        // The mode is saved with the state
+        let mut restored = Calculator::new();
+
+        restored.restore(&c.dump()).unwrap();
+        assert!(restored.is_degrees());
+
+        c.eval("RAD PI 2 / SIN").unwrap();
+
+        assert!(!c.is_degrees());
+        assert_eq!(c.stack().values(), &[Token::Integer(1), Token::Integer(45), Token::Integer(1)]);
+    }
+
+    #[test]
+    fn test_ops() {
+        let mut c = Calculator::new();
+
+        c.register(&DIST).unwrap();
+        c.eval("OPS").unwrap();
+
+        let output = c.take_output();
+        let lines = output[0].lines().collect::<Vec<&str>>();
 
+        assert!(lines[0].starts_with("arithmetic : + - * /"));
+        assert!(lines.contains(&"commands : SAVE LOAD HELP OPS CONST CONSTS DEG RAD"));
+        assert_eq!(lines.last(), Some(&"custom : DIST"));
+    }
 }
@@ -0,0 +1,193 @@
+use pascaline::DEFAULT_CAPACITY;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use toml::{ Table, Value };
+
+// Levels shown by default in the interactive mode, deeper ones being summed up
+pub const DEFAULT_LEVELS: usize = 10;
+// Lines kept in the history of the interactive mode, as rustyline does by default
+pub const DEFAULT_HISTORY: usize = 100;
+
+// Keys understood in the configuration file, listed when an unknown one is found
+const KEYS: &[&str] = &["atomic", "ieee", "angle", "strict", "capacity", "levels", "history", "color", "prelude", "aliases"];
+const UNBOUNDED: &str = "unbounded";
+const DEGREES: &str = "deg";
+const RADIANS: &str = "rad";
+
+/// Defaults read from the configuration file, which command line options override
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub atomic: bool,
+    pub ieee: bool,
+    pub degrees: bool,
+    pub strict: bool,
+    pub capacity: Option<usize>,
+    pub levels: usize,
+    pub history: usize,
+    pub color: bool,
+    pub prelude: Option<PathBuf>,
+    // Other names for operators, along with their symbol
+    pub aliases: Vec<(String, String)>
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            atomic: false,
+            ieee: false,
+            degrees: false,
+            strict: false,
+            capacity: Some(DEFAULT_CAPACITY),
+            levels: DEFAULT_LEVELS,
+            history: DEFAULT_HISTORY,
+            color: true,
+            prelude: None,
+            aliases: Vec::new()
+        }
+    }
+}
+
+fn expect<T>(key: &str, value: Option<T>, kind: &str, warnings: &mut Vec<String>) -> Option<T> {
+    if value.is_none() {
+        warnings.push(format!("'{}' must be {}, ignoring it", key, kind));
+    }
+
+    value
+}
+
+fn count(value: &Value) -> Option<usize> {
+    value.as_integer().and_then(|i| usize::try_from(i).ok())
+}
+
+impl Config {
+    /// Reads the configuration, along with warnings about the keys which were ignored
+    ///
+    /// A relative prelude is looked up next to the configuration file.
+    pub fn parse(text: &str, dir: &Path) -> Result<(Config, Vec<String>), String> {
+        let table = text.parse::<Table>().map_err(|e| e.message().to_string())?;
+        let mut config = Config::default();
+        let mut warnings = Vec::new();
+
+        for (key, value) in &table {
+            let w = &mut warnings;
+
+            // Wrong values are skipped one by one, so a single typo does not lose the whole file
+            match key.as_str() {
+                "atomic" => if let Some(b) = expect(key, value.as_bool(), "a boolean", w) {
+                    config.atomic = b;
+                },
+                "ieee" => if let Some(b) = expect(key, value.as_bool(), "a boolean", w) {
+                    config.ieee = b;
+                },
+                "angle" => {
+                    let degrees = match value.as_str() {
+                        Some(DEGREES) => Some(true),
+                        Some(RADIANS) => Some(false),
+                        _ => None
+                    };
+
+                    if let Some(d) = expect(key, degrees, "\"deg\" or \"rad\"", w) {
+                        config.degrees = d;
+                    }
+                },
+                "strict" => if let Some(b) = expect(key, value.as_bool(), "a boolean", w) {
+                    config.strict = b;
+                },
+                "capacity" => {
+                    let capacity = match value.as_str() {
+                        Some(UNBOUNDED) => Some(None),
+                        _ => count(value).filter(|c| *c > 0).map(Some)
+                    };
+
+                    if let Some(c) = expect(key, capacity, "a positive integer or \"unbounded\"", w) {
+                        config.capacity = c;
+                    }
+                },
+                "levels" => if let Some(l) = expect(key, count(value).filter(|l| *l > 0), "a positive integer", w) {
+                    config.levels = l;
+                },
+                "history" => if let Some(h) = expect(key, count(value), "a non-negative integer", w) {
+                    config.history = h;
+                },
+                "color" => if let Some(b) = expect(key, value.as_bool(), "a boolean", w) {
+                    config.color = b;
+                },
+                "prelude" => if let Some(p) = expect(key, value.as_str(), "a path", w) {
+                    config.prelude = Some(dir.join(p));
+                },
+                "aliases" => if let Some(aliases) = expect(key, value.as_table(), "a table", w) {
+                    for (alias, symbol) in aliases {
+                        match symbol.as_str() {
+                            Some(s) => config.aliases.push((alias.clone(), s.to_string())),
+                            None => w.push(format!("Alias '{}' must be the symbol of an operator, ignoring it", alias))
+                        }
+                    }
+                },
+                _ => w.push(format!("Unknown key '{}', valid keys are : {}", key, KEYS.join(", ")))
+            }
+        }
+
+        Ok((config, warnings))
+    }
+
+    /// Reads the configuration file, falling back on the defaults if there is none
+    pub fn load(path: &Path) -> Result<(Config, Vec<String>), String> {
+        let text = match fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Config::default(), Vec::new())),
+            Err(e) => return Err(e.to_string())
+        };
+
+        Config::parse(&text, path.parent().unwrap_or_else(|| Path::new("")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{ Config, DEFAULT_LEVELS };
+    use std::path::Path;
+
+    #[test]
+    fn test_parse() {
+        let text = "atomic = true\nangle = \"deg\"\nstrict = true\ncapacity = \"unbounded\"\nhistory = 20\nprelude = \"words.pasc\"\n";
+        let (config, warnings) = Config::parse(text, Path::new("/etc/pascaline")).unwrap();
+
+        assert!(config.atomic);
+        assert!(!config.ieee);
+        assert!(config.degrees);
+        assert!(config.strict);
+        assert_eq!(config.capacity, None);
+        assert_eq!(config.levels, DEFAULT_LEVELS);
+        assert_eq!(config.history, 20);
+        assert_eq!(config.prelude.as_deref(), Some(Path::new("/etc/pascaline/words.pasc")));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_aliases() {
+        let text = "[aliases]\nsq = \"SQRT\"\n\"√\" = \"SQRT\"\nbad = 2\n";
+        let (config, warnings) = Config::parse(text, Path::new("")).unwrap();
+
+        assert_eq!(config.aliases, vec![
+            (String::from("sq"), String::from("SQRT")),
+            (String::from("√"), String::from("SQRT"))
+        ]);
+        assert_eq!(warnings, vec!["Alias 'bad' must be the symbol of an operator, ignoring it"]);
+    }
+
+    #[test]
+    fn test_warnings() {
+        let (config, warnings) = Config::parse("base = 16\nangle = \"grad\"\nlevels = -3\nieee = true", Path::new("")).unwrap();
+
+        // Wrong keys are ignored, the others still apply
+        assert!(config.ieee);
+        assert_eq!(config.levels, DEFAULT_LEVELS);
+        assert_eq!(warnings, vec![
+            "'angle' must be \"deg\" or \"rad\", ignoring it",
+            "Unknown key 'base', valid keys are : atomic, ieee, angle, strict, capacity, levels, history, color, prelude, aliases",
+            "'levels' must be a positive integer, ignoring it"
+        ]);
+        assert!(Config::parse("atomic = ", Path::new("")).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use super::operator::{ Category, Metadata };
+use super::symbols::{ CONST, CONSTS, DEG, HELP, LOAD, OPS, RAD, SAVE };
+
+/// Commands, which act on the calculator itself rather than on the stack
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Command {
+    Save,
+    Load,
+    Help,
+    Ops,
+    Const,
+    Consts,
+    Deg,
+    Rad
+}
+
+// Built-in commands along with their documentation
+// Their effect is how they are written, as they take no operand from the stack
+static BUILTINS: &[(Command, Metadata)] = &[
+    (Command::Save, Metadata {
+        symbol: SAVE,
+        arity: 0,
+        category: Category::Command,
+        description: "Saves the whole state to a file, quoting paths with spaces",
+        effect: "SAVE file",
+        examples: &[]
+    }),
+    (Command::Load, Metadata {
+        symbol: LOAD,
+        arity: 0,
+        category: Category::Command,
+        description: "Replaces the whole state with the one saved in a file",
+        effect: "LOAD file",
+        examples: &[]
+    }),
+    (Command::Help, Metadata {
+        symbol: HELP,
+        arity: 0,
+        category: Category::Command,
+        description: "Describes an operator, a command or a constant",
+        effect: "HELP symbol",
+        examples: &[]
+    }),
+    (Command::Ops, Metadata {
+        symbol: OPS,
+        arity: 0,
+        category: Category::Command,
+        description: "Lists the operators and commands by category",
+        effect: "OPS",
+        examples: &[]
+    }),
+    (Command::Const, Metadata {
+        symbol: CONST,
+        arity: 0,
+        category: Category::Command,
+        description: "Pushes a constant, physical ones being named with or without their underscore",
+        effect: "CONST name",
+        examples: &["CONST c"]
+    }),
+    (Command::Consts, Metadata {
+        symbol: CONSTS,
+        arity: 0,
+        category: Category::Command,
+        description: "Lists the constants, with their unit and uncertainty",
+        effect: "CONSTS",
+        examples: &[]
+    }),
+    (Command::Deg, Metadata {
+        symbol: DEG,
+        arity: 0,
+        category: Category::Command,
+        description: "Switches angles to degrees, for the trigonometric functions and their inverses",
+        effect: "DEG",
+        examples: &["DEG 90 SIN"]
+    }),
+    (Command::Rad, Metadata {
+        symbol: RAD,
+        arity: 0,
+        category: Category::Command,
+        description: "Switches angles back to radians, the default",
+        effect: "RAD",
+        examples: &[]
+    })
+];
+
+impl Command {
+    /// Finds the command matching a symbol
+    pub fn from_symbol(symbol: &str) -> Option<Command> {
+        BUILTINS.iter().find(|(_, m)| m.symbol == symbol).map(|(c, _)| *c)
+    }
+
+    /// All the commands, in the order they are listed
+    pub fn all() -> impl Iterator<Item = Command> {
+        BUILTINS.iter().map(|(c, _)| *c)
+    }
+
+    /// Documentation of the command
+    pub fn metadata(&self) -> Metadata {
+        // Safe to unwrap, every command is listed
+        BUILTINS.iter().find(|(c, _)| c == self).map(|(_, m)| *m).unwrap()
+    }
+
+    /// Symbol of the command, as written in texts
+    pub fn symbol(&self) -> &'static str {
+        self.metadata().symbol
+    }
+
+    /// Whether the command takes the next word as argument
+    pub fn takes_argument(&self) -> bool {
+        !matches!(self, Command::Ops | Command::Consts | Command::Deg | Command::Rad)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{ Category, Command };
+    use crate::core::symbols::COMMANDS;
+
+    #[test]
+    fn test_from_symbol() {
+        // Every command symbol has its documentation
+        for symbol in COMMANDS {
+            let command = Command::from_symbol(symbol).unwrap();
+
+            assert_eq!(command.symbol(), *symbol);
+            assert_eq!(command.metadata().category, Category::Command);
+            assert!(!command.metadata().description.is_empty());
+        }
+
+        assert_eq!(Command::all().count(), COMMANDS.len());
+        assert_eq!(Command::from_symbol("DUP"), None);
+    }
+}
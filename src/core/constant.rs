@@ -0,0 +1,362 @@
+use super::token::Token;
+use super::types::Float;
+use super::unit::Unit;
+use super::symbols::{ E, GAMMA, INF, NAN, PHI, PI };
+use std::f64::consts;
+use std::fmt;
+
+/// Named value, pushed whenever its symbol is found
+///
+/// Physical constants come with their unit and standard uncertainty, as published by CODATA,
+/// and are pushed as quantities.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constant {
+    pub symbol: &'static str,
+    pub description: &'static str,
+    // Kept at the highest precision available, then rounded to the one of `Float` when pushed
+    pub value: f64,
+    // SI unit, as parsed by `Unit::parse`, empty for dimensionless values
+    pub unit: &'static str,
+    // Standard uncertainty, 0 for exact values
+    pub uncertainty: f64
+}
+
+/// Mathematical constants, known by every calculator
+pub static MATHEMATICAL: &[Constant] = &[
+    Constant {
+        symbol: PI,
+        description: "Ratio of the circumference of a circle to its diameter",
+        value: consts::PI,
+        unit: "",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: E,
+        description: "Base of the natural logarithm",
+        value: consts::E,
+        unit: "",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: PHI,
+        description: "Golden ratio",
+        value: 1.618_033_988_749_895,
+        unit: "",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: GAMMA,
+        description: "Euler-Mascheroni constant",
+        value: 0.577_215_664_901_532_9,
+        unit: "",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: INF,
+        description: "Positive infinity, only useful in IEEE mode",
+        value: f64::INFINITY,
+        unit: "",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: NAN,
+        description: "Not a number, only useful in IEEE mode",
+        value: f64::NAN,
+        unit: "",
+        uncertainty: 0.0
+    }
+];
+
+/// Physical constants, from the CODATA 2022 recommended values
+///
+/// Their symbols start with an underscore, so they never collide with operators.
+pub static PHYSICAL: &[Constant] = &[
+    Constant {
+        symbol: "_c",
+        description: "Speed of light in vacuum",
+        value: 299_792_458.0,
+        unit: "m/s",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_h",
+        description: "Planck constant",
+        value: 6.626_070_15e-34,
+        unit: "J*s",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_hbar",
+        description: "Reduced Planck constant",
+        value: 1.054_571_817e-34,
+        unit: "J*s",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_e",
+        description: "Elementary charge",
+        value: 1.602_176_634e-19,
+        unit: "C",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_kB",
+        description: "Boltzmann constant",
+        value: 1.380_649e-23,
+        unit: "J/K",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_NA",
+        description: "Avogadro constant",
+        value: 6.022_140_76e23,
+        unit: "1/mol",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_R",
+        description: "Molar gas constant",
+        value: 8.314_462_618,
+        unit: "J/mol*K",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_F",
+        description: "Faraday constant",
+        value: 96_485.332_12,
+        unit: "C/mol",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_sigma",
+        description: "Stefan-Boltzmann constant",
+        value: 5.670_374_419e-8,
+        unit: "W/m^2*K^4",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_G",
+        description: "Newtonian constant of gravitation",
+        value: 6.674_30e-11,
+        unit: "m^3/kg*s^2",
+        uncertainty: 1.5e-15
+    },
+    Constant {
+        symbol: "_g",
+        description: "Standard acceleration of gravity",
+        value: 9.806_65,
+        unit: "m/s^2",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_atm",
+        description: "Standard atmosphere",
+        value: 101_325.0,
+        unit: "Pa",
+        uncertainty: 0.0
+    },
+    Constant {
+        symbol: "_me",
+        description: "Electron mass",
+        value: 9.109_383_713_9e-31,
+        unit: "kg",
+        uncertainty: 2.8e-40
+    },
+    Constant {
+        symbol: "_mp",
+        description: "Proton mass",
+        value: 1.672_621_925_95e-27,
+        unit: "kg",
+        uncertainty: 5.2e-37
+    },
+    Constant {
+        symbol: "_mn",
+        description: "Neutron mass",
+        value: 1.674_927_500_56e-27,
+        unit: "kg",
+        uncertainty: 8.5e-37
+    },
+    Constant {
+        symbol: "_u",
+        description: "Atomic mass constant",
+        value: 1.660_539_068_92e-27,
+        unit: "kg",
+        uncertainty: 5.2e-37
+    },
+    Constant {
+        symbol: "_eps0",
+        description: "Vacuum electric permittivity",
+        value: 8.854_187_818_8e-12,
+        unit: "C/V*m",
+        uncertainty: 1.4e-21
+    },
+    Constant {
+        symbol: "_mu0",
+        description: "Vacuum magnetic permeability",
+        value: 1.256_637_061_27e-6,
+        unit: "N/A^2",
+        uncertainty: 2.0e-16
+    },
+    Constant {
+        symbol: "_alpha",
+        description: "Fine-structure constant",
+        value: 7.297_352_564_3e-3,
+        unit: "",
+        uncertainty: 1.1e-12
+    },
+    Constant {
+        symbol: "_Rinf",
+        description: "Rydberg constant",
+        value: 10_973_731.568_157,
+        unit: "1/m",
+        uncertainty: 1.2e-5
+    },
+    Constant {
+        symbol: "_a0",
+        description: "Bohr radius",
+        value: 5.291_772_105_44e-11,
+        unit: "m",
+        uncertainty: 8.2e-21
+    }
+];
+
+// Plain notation for usual magnitudes, scientific notation otherwise
+fn format_value(value: f64) -> String {
+    if value == 0.0 || !value.is_finite() || (1e-3..1e10).contains(&value.abs()) {
+        value.to_string()
+    } else {
+        format!("{:e}", value)
+    }
+}
+
+impl Constant {
+    /// Finds the constant matching a symbol, either mathematical or physical
+    pub fn lookup(symbol: &str) -> Option<&'static Constant> {
+        MATHEMATICAL.iter().chain(PHYSICAL).find(|c| c.symbol == symbol)
+    }
+
+    /// All the constants, mathematical ones first
+    pub fn all() -> impl Iterator<Item = &'static Constant> {
+        MATHEMATICAL.iter().chain(PHYSICAL)
+    }
+
+    /// Whether the value is known exactly, by definition
+    pub fn is_exact(&self) -> bool {
+        self.uncertainty == 0.0
+    }
+
+    /// Token pushed for the constant, at the precision of the values
+    pub fn token(&self) -> Token {
+        // Safe to unwrap, units of the built-in constants are checked by the tests
+        let unit = if self.unit.is_empty() { Unit::none() } else { Unit::parse(self.unit).unwrap() };
+
+        Token::new_quantity(self.value as Float, unit)
+    }
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", format_value(self.value))?;
+
+        if !self.unit.is_empty() {
+            write!(formatter, " {}", self.unit)?;
+        }
+
+        if !self.is_exact() {
+            write!(formatter, " \u{b1} {}", format_value(self.uncertainty))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calculator::Calculator;
+    use crate::core::{ Constant, Token, Unit, PHYSICAL };
+    use crate::core::symbols::{ CONSTANTS, NAN, PI };
+    use crate::errors::{ EvalError, PascalineError };
+
+    #[test]
+    fn test_lookup() {
+        for symbol in CONSTANTS {
+            assert_eq!(Constant::lookup(symbol).map(|c| c.symbol), Some(*symbol));
+        }
+
+        assert_eq!(Constant::lookup(PI).unwrap().token(), Token::new_float(std::f32::consts::PI));
+        assert!(Constant::lookup(NAN).unwrap().token().as_float().unwrap().is_nan());
+        assert_eq!(Constant::lookup("pi"), None);
+    }
+
+    #[test]
+    fn test_physical() {
+        let c = Constant::lookup("_c").unwrap();
+        let g = Constant::lookup("_G").unwrap();
+
+        // Constants with a unit are quantities
+        assert_eq!(c.token(), Token::new_quantity(299_792_458.0, Unit::parse("m/s").unwrap()));
+        assert_eq!(Constant::lookup("_alpha").unwrap().token(), Token::new_float(7.297_352_6e-3));
+        assert_eq!(c.to_string(), "299792458 m/s");
+        assert_eq!(g.to_string(), "6.6743e-11 m^3/kg*s^2 \u{b1} 1.5e-15");
+        assert!(!g.is_exact());
+
+        // Symbols are unique, all physical ones start with an underscore, and their units are valid
+        let mut symbols = Constant::all().map(|c| c.symbol).collect::<Vec<&str>>();
+
+        symbols.sort_unstable();
+        symbols.dedup();
+
+        assert_eq!(symbols.len(), Constant::all().count());
+        assert!(PHYSICAL.iter().all(|c| c.symbol.starts_with('_')));
+        assert!(PHYSICAL.iter().all(|c| c.unit.is_empty() || Unit::parse(c.unit).is_some()));
+    }
+
+    #[test]
+    fn test_eval_physical() {
+        let mut c = Calculator::new();
+
+        c.eval("_g CONST g CONST PI CONSTS").unwrap();
+
+        let g = Token::new_quantity(9.806_65, Unit::parse("m/s^2").unwrap());
+
+        assert_eq!(c.stack().values(), &[g, g, Token::new_float(std::f32::consts::PI)]);
+
+        let output = c.take_output();
+
+        assert!(output[0].lines().any(|l| l == "_c : Speed of light in vacuum = 299792458 m/s, exact"));
+        assert!(output[0].lines().any(|l| l == "_G : Newtonian constant of gravitation = 6.6743e-11 m^3/kg*s^2 \u{b1} 1.5e-15"));
+
+        match c.eval("CONST nope") {
+            Err(EvalError { error: PascalineError::ConstantError(s), .. }) => assert_eq!(s, "nope"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        // Tiny and huge constants are shown with their digits, and units combine like any other
+        c.eval("CLEAR _h _NA _e CONST kB _NA *").unwrap();
+
+        let shown = c.stack().values().iter().map(Token::to_string).collect::<Vec<String>>();
+
+        assert_eq!(shown, vec!["6.626070e-34_s*J", "6.022141e23_1/mol", "1.602177e-19_C", "8.314463_J/K*mol"]);
+    }
+
+    #[test]
+    fn test_eval_mathematical() {
+        let mut c = Calculator::new();
+
+        c.eval("2 PI * PHI GAMMA").unwrap();
+
+        assert_eq!(c.stack().values(), &[
+            Token::new_float(2.0 * std::f32::consts::PI),
+            Token::new_float(1.618_034),
+            Token::new_float(0.577_215_7)
+        ]);
+
+        // Infinite values are only accepted by operators in IEEE mode
+        assert!(c.eval("INF 1 +").is_err());
+
+        c.set_ieee(true);
+        c.eval("CLEAR INF 1 + NAN").unwrap();
+
+        assert_eq!(c.stack().values()[0], Token::new_float(f32::INFINITY));
+    }
+}
@@ -1,12 +1,19 @@
 mod parser;
 mod token;
+mod command;
+mod constant;
+mod unit;
 mod operator;
 mod stack;
+mod registry;
 pub mod symbols;
 pub mod types;
 
-pub use self::parser::Parser;
+pub use self::parser::{ Parser, Span, WordKind };
 pub use self::token::Token;
-pub use self::operator::Operator;
-pub use self::stack::Stack;
-pub use self::symbols::{ OPERATORS, SYMBOLS };
+pub use self::command::Command;
+pub use self::constant::{ Constant, MATHEMATICAL, PHYSICAL };
+pub use self::unit::{ Unit, UnitDef, UNITS };
+pub use self::operator::{ Category, Metadata, Operator };
+pub use self::stack::{ Levels, Stack, DEFAULT_CAPACITY };
+pub use self::registry::{ OperatorImpl, Registry };
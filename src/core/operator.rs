@@ -1,10 +1,17 @@
+use super::registry::OperatorImpl;
 use super::symbols::*;
 use super::token::Token;
 use super::types::Float;
+use super::unit::Unit;
 use crate::errors::PascalineError;
+#[cfg(feature = "serde")]
+use serde::{ de, Deserialize, Deserializer, Serialize, Serializer };
 use std::fmt;
 
-#[derive(Debug, PartialEq)]
+/// Operators, either built-in or provided by other crates through [`OperatorImpl`]
+///
+/// [`OperatorImpl`]: crate::OperatorImpl
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Operator {
     Add,
     Sub,
@@ -20,9 +27,37 @@ pub enum Operator {
     ArcSin,
     ArcCos,
     ArcTan,
+    Sinh,
+    Cosh,
+    Tanh,
+    ArcSinh,
+    ArcCosh,
+    ArcTanh,
+    ArcTan2,
+    Hypot,
     Sqrt,
     Exp,
     Ln,
+    Log,
+    Log2,
+    LogB,
+    ALog,
+    ExpM1,
+    Ln1P,
+    Cbrt,
+    XRoot,
+    // ROUNDING OPERATORS
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Round,
+    Trunc,
+    Frac,
+    Ip,
+    Min,
+    Max,
+    Rnd,
     // Num,
     // Den,
     // COMPLEX OPERATORS
@@ -41,6 +76,10 @@ pub enum Operator {
     And,
     Or,
     Not,
+    // UNIT OPERATORS
+    Convert,
+    UBase,
+    UVal,
     // STACK OPERATORS
     Dup,
     Drop,
@@ -49,403 +88,1658 @@ pub enum Operator {
     LastArgs,
     Undo,
     Redo,
-    Clear
-}
-
-// Declaring operators once
-const ADD_OPERATOR: Operator = Operator::Add;
-const SUB_OPERATOR: Operator = Operator::Sub;
-const MUL_OPERATOR: Operator = Operator::Mul;
-const DIV_OPERATOR: Operator = Operator::Div;
-const EUC_DIV_OPERATOR: Operator = Operator::EucDiv;
-const MOD_OPERATOR: Operator = Operator::Mod;
-const POW_OPERATOR: Operator = Operator::Pow;
-const NEG_OPERATOR: Operator = Operator::Neg;
-const SIN_OPERATOR: Operator = Operator::Sin;
-const COS_OPERATOR: Operator = Operator::Cos;
-const TAN_OPERATOR: Operator = Operator::Tan;
-const ARCSIN_OPERATOR: Operator = Operator::ArcSin;
-const ARCCOS_OPERATOR: Operator = Operator::ArcCos;
-const ARCTAN_OPERATOR: Operator = Operator::ArcTan;
-const SQRT_OPERATOR: Operator = Operator::Sqrt;
-const EXP_OPERATOR: Operator = Operator::Exp;
-const LN_OPERATOR: Operator = Operator::Ln;
-// const NUM_OPERATOR: Operator = Operator::Num;
-// const DEN_OPERATOR: Operator = Operator::Den;
-// const COMPLEX_OPERATOR: Operator = Operator::Complex;
-// const RE_OPERATOR: Operator = Operator::Re;
-// const IM_OPERATOR: Operator = Operator::Im;
-// const ARG_OPERATOR: Operator = Operator::Arg;
-// const NORM_OPERATOR: Operator = Operator::Norm;
-const EQ_OPERATOR: Operator = Operator::Eq;
-const NEQ_OPERATOR: Operator = Operator::Neq;
-const LE_OPERATOR: Operator = Operator::Le;
-const LT_OPERATOR: Operator = Operator::Lt;
-const GE_OPERATOR: Operator = Operator::Ge;
-const GT_OPERATOR: Operator = Operator::Gt;
-const AND_OPERATOR: Operator = Operator::And;
-const OR_OPERATOR: Operator = Operator::Or;
-const NOT_OPERATOR: Operator = Operator::Not;
-const DUP_OPERATOR: Operator = Operator::Dup;
-const DROP_OPERATOR: Operator = Operator::Drop;
-const SWAP_OPERATOR: Operator = Operator::Swap;
-const LASTOP_OPERATOR: Operator = Operator::LastOp;
-const LASTARGS_OPERATOR: Operator = Operator::LastArgs;
-const UNDO_OPERATOR: Operator = Operator::Undo;
-const REDO_OPERATOR: Operator = Operator::Redo;
-const CLEAR_OPERATOR: Operator = Operator::Clear;
+    Clear,
+    // CUSTOM OPERATORS
+    Custom(&'static dyn OperatorImpl)
+}
+
+/// Family of an operator, used to group them in listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Arithmetic,
+    Trigonometry,
+    Logic,
+    Units,
+    Stack,
+    Command,
+    Custom
+}
+
+impl Category {
+    /// All the categories, in the order they are listed
+    pub const ALL: [Category; 7] = [
+        Category::Arithmetic,
+        Category::Trigonometry,
+        Category::Logic,
+        Category::Units,
+        Category::Stack,
+        Category::Command,
+        Category::Custom
+    ];
+
+    /// Name of the category, as shown to users
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Arithmetic => "arithmetic",
+            Category::Trigonometry => "trigonometry",
+            Category::Logic => "logic",
+            Category::Units => "units",
+            Category::Stack => "stack",
+            Category::Command => "commands",
+            Category::Custom => "custom"
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.name())
+    }
+}
+
+/// Documentation of an operator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metadata {
+    /// Symbol of the operator, as written in texts
+    pub symbol: &'static str,
+    /// Number of operands taken from the stack
+    pub arity: usize,
+    /// Family of the operator
+    pub category: Category,
+    /// One-line description
+    pub description: &'static str,
+    /// Effect on the stack, such as `( x y -- x+y )`
+    pub effect: &'static str,
+    /// Texts showing how to use the operator
+    pub examples: &'static [&'static str]
+}
+
+// Computation of a built-in operator, given operands already checked against its arity and type
+type Apply = fn(&[Token]) -> Result<Token, PascalineError>;
+
+// Built-in operators along with their documentation and computation
+// This is the only place where symbols and arities are defined, so they cannot drift apart
+static BUILTINS: &[(Operator, Metadata, Apply)] = &[
+    (Operator::Add, Metadata {
+        symbol: ADD,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Adds two numbers",
+        effect: "( x y -- x+y )",
+        examples: &["2 3 +"]
+    }, add),
+    (Operator::Sub, Metadata {
+        symbol: SUB,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Subtracts the top number from the one below",
+        effect: "( x y -- x-y )",
+        examples: &["5 3 -"]
+    }, sub),
+    (Operator::Mul, Metadata {
+        symbol: MUL,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Multiplies two numbers",
+        effect: "( x y -- x*y )",
+        examples: &["4 2.5 *"]
+    }, mul),
+    (Operator::Div, Metadata {
+        symbol: DIV,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Divides the number below by the top one",
+        effect: "( x y -- x/y )",
+        examples: &["7 2 /"]
+    }, div),
+    (Operator::EucDiv, Metadata {
+        symbol: EUC_DIV,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Euclidean division, rounding the quotient so the remainder is positive",
+        effect: "( x y -- q )",
+        examples: &["7 2 DIV", "-7 2 DIV"]
+    }, euc_div),
+    (Operator::Mod, Metadata {
+        symbol: MOD,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Remainder of the euclidean division, always positive",
+        effect: "( x y -- r )",
+        examples: &["7 3 MOD", "-7 3 MOD"]
+    }, modulo),
+    (Operator::Pow, Metadata {
+        symbol: POW,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Raises the number below to the power of the top one",
+        effect: "( x y -- x^y )",
+        examples: &["2 10 POW", "9 0.5 POW"]
+    }, pow),
+    (Operator::Neg, Metadata {
+        symbol: NEG,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Negates a number",
+        effect: "( x -- -x )",
+        examples: &["3 NEG"]
+    }, neg),
+    (Operator::Sin, Metadata {
+        symbol: SIN,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Sine of an angle, in radians or in degrees depending on the angle mode",
+        effect: "( x -- sin(x) )",
+        examples: &["0 SIN"]
+    }, sin),
+    (Operator::Cos, Metadata {
+        symbol: COS,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Cosine of an angle, in radians or in degrees depending on the angle mode",
+        effect: "( x -- cos(x) )",
+        examples: &["0 COS"]
+    }, cos),
+    (Operator::Tan, Metadata {
+        symbol: TAN,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Tangent of an angle, in radians or in degrees depending on the angle mode",
+        effect: "( x -- tan(x) )",
+        examples: &["0 TAN"]
+    }, tan),
+    (Operator::ArcSin, Metadata {
+        symbol: ARCSIN,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Arc sine, in radians or in degrees depending on the angle mode",
+        effect: "( x -- asin(x) )",
+        examples: &["1 ARCSIN"]
+    }, arc_sin),
+    (Operator::ArcCos, Metadata {
+        symbol: ARCCOS,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Arc cosine, in radians or in degrees depending on the angle mode",
+        effect: "( x -- acos(x) )",
+        examples: &["1 ARCCOS"]
+    }, arc_cos),
+    (Operator::ArcTan, Metadata {
+        symbol: ARCTAN,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Arc tangent, in radians or in degrees depending on the angle mode",
+        effect: "( x -- atan(x) )",
+        examples: &["1 ARCTAN"]
+    }, arc_tan),
+    (Operator::Sinh, Metadata {
+        symbol: SINH,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Hyperbolic sine",
+        effect: "( x -- sinh(x) )",
+        examples: &["0 SINH"]
+    }, sinh),
+    (Operator::Cosh, Metadata {
+        symbol: COSH,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Hyperbolic cosine",
+        effect: "( x -- cosh(x) )",
+        examples: &["0 COSH"]
+    }, cosh),
+    (Operator::Tanh, Metadata {
+        symbol: TANH,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Hyperbolic tangent",
+        effect: "( x -- tanh(x) )",
+        examples: &["0 TANH"]
+    }, tanh),
+    (Operator::ArcSinh, Metadata {
+        symbol: ASINH,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Inverse hyperbolic sine",
+        effect: "( x -- asinh(x) )",
+        examples: &["0 ASINH"]
+    }, arc_sinh),
+    (Operator::ArcCosh, Metadata {
+        symbol: ACOSH,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Inverse hyperbolic cosine of a number greater than or equal to 1",
+        effect: "( x -- acosh(x) )",
+        examples: &["1 ACOSH"]
+    }, arc_cosh),
+    (Operator::ArcTanh, Metadata {
+        symbol: ATANH,
+        arity: 1,
+        category: Category::Trigonometry,
+        description: "Inverse hyperbolic tangent of a number strictly between -1 and 1",
+        effect: "( x -- atanh(x) )",
+        examples: &["0 ATANH"]
+    }, arc_tanh),
+    (Operator::ArcTan2, Metadata {
+        symbol: ATAN2,
+        arity: 2,
+        category: Category::Trigonometry,
+        description: "Angle of the point (x, y) from the x axis, between -pi and pi radians (or -180 and 180 degrees), 0 at the origin",
+        effect: "( y x -- atan2(y,x) )",
+        examples: &["1 -1 ATAN2"]
+    }, arc_tan2),
+    (Operator::Hypot, Metadata {
+        symbol: HYPOT,
+        arity: 2,
+        category: Category::Trigonometry,
+        description: "Length of the hypotenuse of a right triangle, without intermediate overflow",
+        effect: "( x y -- sqrt(x^2+y^2) )",
+        examples: &["3 4 HYPOT"]
+    }, hypot),
+    (Operator::Sqrt, Metadata {
+        symbol: SQRT,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Square root of a positive number",
+        effect: "( x -- sqrt(x) )",
+        examples: &["16 SQRT"]
+    }, sqrt),
+    (Operator::Exp, Metadata {
+        symbol: EXP,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Exponential of a number",
+        effect: "( x -- e^x )",
+        examples: &["1 EXP"]
+    }, exp),
+    (Operator::Ln, Metadata {
+        symbol: LN,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Natural logarithm of a strictly positive number",
+        effect: "( x -- ln(x) )",
+        examples: &["1 LN"]
+    }, ln),
+    (Operator::Log, Metadata {
+        symbol: LOG,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Base 10 logarithm of a strictly positive number",
+        effect: "( x -- log10(x) )",
+        examples: &["1000 LOG"]
+    }, log),
+    (Operator::Log2, Metadata {
+        symbol: LOG2,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Base 2 logarithm of a strictly positive number",
+        effect: "( x -- log2(x) )",
+        examples: &["1024 LOG2"]
+    }, log2),
+    (Operator::LogB, Metadata {
+        symbol: LOGB,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Logarithm of the number below in the base on top, both strictly positive and the base different from 1",
+        effect: "( x b -- logb(x) )",
+        examples: &["81 3 LOGB"]
+    }, logb),
+    (Operator::ALog, Metadata {
+        symbol: ALOG,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Raises 10 to the power of a number",
+        effect: "( x -- 10^x )",
+        examples: &["3 ALOG"]
+    }, alog),
+    (Operator::ExpM1, Metadata {
+        symbol: EXPM1,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Exponential of a number minus 1, accurate near 0",
+        effect: "( x -- e^x-1 )",
+        examples: &["0.000001 EXPM1"]
+    }, expm1),
+    (Operator::Ln1P, Metadata {
+        symbol: LN1P,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Natural logarithm of 1 plus a number greater than -1, accurate near 0",
+        effect: "( x -- ln(1+x) )",
+        examples: &["0.000001 LN1P"]
+    }, ln1p),
+    (Operator::Cbrt, Metadata {
+        symbol: CBRT,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Cube root of a number, negative ones included",
+        effect: "( x -- cbrt(x) )",
+        examples: &["-27 CBRT"]
+    }, cbrt),
+    (Operator::XRoot, Metadata {
+        symbol: XROOT,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Root of the number below to the degree on top, negative numbers only having odd integer roots",
+        effect: "( x n -- x^(1/n) )",
+        examples: &["32 5 XROOT", "-8 3 XROOT"]
+    }, xroot),
+    (Operator::Abs, Metadata {
+        symbol: ABS,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Absolute value of a number",
+        effect: "( x -- |x| )",
+        examples: &["-2.5 ABS"]
+    }, abs),
+    (Operator::Sign, Metadata {
+        symbol: SIGN,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Sign of a number, as -1, 0 or 1",
+        effect: "( x -- sign(x) )",
+        examples: &["-2.5 SIGN", "0 SIGN"]
+    }, sign),
+    (Operator::Floor, Metadata {
+        symbol: FLOOR,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Greatest integer lower than or equal to a number, rounding towards minus infinity",
+        effect: "( x -- floor(x) )",
+        examples: &["2.5 FLOOR", "-2.5 FLOOR"]
+    }, floor),
+    (Operator::Ceil, Metadata {
+        symbol: CEIL,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Lowest integer greater than or equal to a number, rounding towards plus infinity",
+        effect: "( x -- ceil(x) )",
+        examples: &["2.5 CEIL", "-2.5 CEIL"]
+    }, ceil),
+    (Operator::Round, Metadata {
+        symbol: ROUND,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Nearest integer, halves being rounded away from zero",
+        effect: "( x -- round(x) )",
+        examples: &["2.5 ROUND", "-2.5 ROUND"]
+    }, round),
+    (Operator::Trunc, Metadata {
+        symbol: TRUNC,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Integer part of a number, rounding towards zero",
+        effect: "( x -- trunc(x) )",
+        examples: &["2.5 TRUNC", "-2.5 TRUNC"]
+    }, trunc),
+    (Operator::Frac, Metadata {
+        symbol: FRAC,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Fractional part of a number, with the sign of the number",
+        effect: "( x -- x-trunc(x) )",
+        examples: &["2.5 FRAC", "-2.5 FRAC"]
+    }, frac),
+    (Operator::Ip, Metadata {
+        symbol: IP,
+        arity: 1,
+        category: Category::Arithmetic,
+        description: "Integer part of a number, same as TRUNC",
+        effect: "( x -- ip(x) )",
+        examples: &["-2.5 IP"]
+    }, trunc),
+    (Operator::Min, Metadata {
+        symbol: MIN,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Lowest of two numbers",
+        effect: "( x y -- min(x,y) )",
+        examples: &["2 -3.5 MIN"]
+    }, min),
+    (Operator::Max, Metadata {
+        symbol: MAX,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Greatest of two numbers",
+        effect: "( x y -- max(x,y) )",
+        examples: &["2 -3.5 MAX"]
+    }, max),
+    (Operator::Rnd, Metadata {
+        symbol: RND,
+        arity: 2,
+        category: Category::Arithmetic,
+        description: "Rounds the number below to the top number of decimals, negative ones rounding to tens, hundreds, ...",
+        effect: "( x n -- x' )",
+        examples: &["1.23456 2 RND", "1234 -2 RND"]
+    }, rnd),
+    (Operator::Eq, Metadata {
+        symbol: EQ,
+        arity: 2,
+        category: Category::Logic,
+        description: "Whether two numbers are equal",
+        effect: "( x y -- x==y )",
+        examples: &["2 2.0 =="]
+    }, eq),
+    (Operator::Neq, Metadata {
+        symbol: NEQ,
+        arity: 2,
+        category: Category::Logic,
+        description: "Whether two numbers are different",
+        effect: "( x y -- x!=y )",
+        examples: &["2 3 !="]
+    }, neq),
+    (Operator::Le, Metadata {
+        symbol: LE,
+        arity: 2,
+        category: Category::Logic,
+        description: "Whether the number below is lower than or equal to the top one",
+        effect: "( x y -- x<=y )",
+        examples: &["2 3 <="]
+    }, le),
+    (Operator::Lt, Metadata {
+        symbol: LT,
+        arity: 2,
+        category: Category::Logic,
+        description: "Whether the number below is lower than the top one",
+        effect: "( x y -- x<y )",
+        examples: &["3 3 <"]
+    }, lt),
+    (Operator::Ge, Metadata {
+        symbol: GE,
+        arity: 2,
+        category: Category::Logic,
+        description: "Whether the number below is greater than or equal to the top one",
+        effect: "( x y -- x>=y )",
+        examples: &["3 3 >="]
+    }, ge),
+    (Operator::Gt, Metadata {
+        symbol: GT,
+        arity: 2,
+        category: Category::Logic,
+        description: "Whether the number below is greater than the top one",
+        effect: "( x y -- x>y )",
+        examples: &["2 3 >"]
+    }, gt),
+    (Operator::And, Metadata {
+        symbol: AND,
+        arity: 2,
+        category: Category::Logic,
+        description: "Logical and of two booleans",
+        effect: "( a b -- a&&b )",
+        examples: &["TRUE FALSE AND"]
+    }, and),
+    (Operator::Or, Metadata {
+        symbol: OR,
+        arity: 2,
+        category: Category::Logic,
+        description: "Logical or of two booleans",
+        effect: "( a b -- a||b )",
+        examples: &["TRUE FALSE OR"]
+    }, or),
+    (Operator::Not, Metadata {
+        symbol: NOT,
+        arity: 1,
+        category: Category::Logic,
+        description: "Logical negation of a boolean",
+        effect: "( a -- !a )",
+        examples: &["TRUE NOT"]
+    }, not),
+    (Operator::Convert, Metadata {
+        symbol: CONVERT,
+        arity: 2,
+        category: Category::Units,
+        description: "Converts a value to the unit of the top one, whose value is ignored",
+        effect: "( x_u y_v -- x'_v )",
+        examples: &["1_in 1_mm CONVERT", "60_mph 1_m/s CONVERT"]
+    }, convert),
+    (Operator::UBase, Metadata {
+        symbol: UBASE,
+        arity: 1,
+        category: Category::Units,
+        description: "Converts a value to SI base units",
+        effect: "( x_u -- x'_b )",
+        examples: &["1_kWh UBASE", "1_km/h UBASE"]
+    }, ubase),
+    (Operator::UVal, Metadata {
+        symbol: UVAL,
+        arity: 1,
+        category: Category::Units,
+        description: "Removes the unit of a value",
+        effect: "( x_u -- x )",
+        examples: &["9.81_m/s^2 UVAL"]
+    }, uval),
+    (Operator::Dup, Metadata {
+        symbol: DUP,
+        arity: 0,
+        category: Category::Stack,
+        description: "Duplicates the value on top of the stack",
+        effect: "( x -- x x )",
+        examples: &["1 2 DUP"]
+    }, stack_only),
+    (Operator::Drop, Metadata {
+        symbol: DROP,
+        arity: 0,
+        category: Category::Stack,
+        description: "Removes the value on top of the stack",
+        effect: "( x -- )",
+        examples: &["1 2 DROP"]
+    }, stack_only),
+    (Operator::Swap, Metadata {
+        symbol: SWAP,
+        arity: 0,
+        category: Category::Stack,
+        description: "Swaps the two values on top of the stack",
+        effect: "( x y -- y x )",
+        examples: &["1 2 SWAP"]
+    }, stack_only),
+    (Operator::LastOp, Metadata {
+        symbol: LASTOP,
+        arity: 0,
+        category: Category::Stack,
+        description: "Applies the last operator again",
+        effect: "( ... -- ... )",
+        examples: &["2 3 4 * LASTOP"]
+    }, stack_only),
+    (Operator::LastArgs, Metadata {
+        symbol: LASTARGS,
+        arity: 0,
+        category: Category::Stack,
+        description: "Pushes back the operands of the last operator (not implemented yet)",
+        effect: "( -- ... )",
+        examples: &[]
+    }, stack_only),
+    (Operator::Undo, Metadata {
+        symbol: UNDO,
+        arity: 0,
+        category: Category::Stack,
+        description: "Reverts the last change (not implemented yet)",
+        effect: "( ... -- ... )",
+        examples: &[]
+    }, stack_only),
+    (Operator::Redo, Metadata {
+        symbol: REDO,
+        arity: 0,
+        category: Category::Stack,
+        description: "Applies the last reverted change again (not implemented yet)",
+        effect: "( ... -- ... )",
+        examples: &[]
+    }, stack_only),
+    (Operator::Clear, Metadata {
+        symbol: CLEAR,
+        arity: 0,
+        category: Category::Stack,
+        description: "Removes all the values",
+        effect: "( ... -- )",
+        examples: &["1 2 3 CLEAR"]
+    }, stack_only)
+];
 
 impl Operator {
-    pub fn from_symbol(symbol: &str) -> Result<&Operator, PascalineError> {
-        match symbol {
-            ADD => Ok(&ADD_OPERATOR),
-            SUB => Ok(&SUB_OPERATOR),
-            MUL => Ok(&MUL_OPERATOR),
-            DIV => Ok(&DIV_OPERATOR),
-            EUC_DIV => Ok(&EUC_DIV_OPERATOR),
-            MOD => Ok(&MOD_OPERATOR),
-            POW => Ok(&POW_OPERATOR),
-            NEG => Ok(&NEG_OPERATOR),
-            SIN => Ok(&SIN_OPERATOR),
-            COS => Ok(&COS_OPERATOR),
-            TAN => Ok(&TAN_OPERATOR),
-            ARCSIN => Ok(&ARCSIN_OPERATOR),
-            ARCCOS => Ok(&ARCCOS_OPERATOR),
-            ARCTAN => Ok(&ARCTAN_OPERATOR),
-            SQRT => Ok(&SQRT_OPERATOR),
-            EXP => Ok(&EXP_OPERATOR),
-            LN => Ok(&LN_OPERATOR),
-            // NUM => Ok(&NUM_OPERATOR),
-            // DEN => Ok(&DEN_OPERATOR),
-            // COMPLEX => Ok(&COMPLEX_OPERATOR),
-            // RE => Ok(&RE_OPERATOR),
-            // IM => Ok(&IM_OPERATOR),
-            // ARG => Ok(&ARG_OPERATOR),
-            // NORM => Ok(&NORM_OPERATOR),
-            EQ => Ok(&EQ_OPERATOR),
-            NEQ => Ok(&NEQ_OPERATOR),
-            LE => Ok(&LE_OPERATOR),
-            LT => Ok(&LT_OPERATOR),
-            GE => Ok(&GE_OPERATOR),
-            GT => Ok(&GT_OPERATOR),
-            AND => Ok(&AND_OPERATOR),
-            OR => Ok(&OR_OPERATOR),
-            NOT => Ok(&NOT_OPERATOR),
-            DUP => Ok(&DUP_OPERATOR),
-            DROP => Ok(&DROP_OPERATOR),
-            SWAP => Ok(&SWAP_OPERATOR),
-            LASTOP => Ok(&LASTOP_OPERATOR),
-            LASTARGS => Ok(&LASTARGS_OPERATOR),
-            UNDO => Ok(&UNDO_OPERATOR),
-            REDO => Ok(&REDO_OPERATOR),
-            CLEAR => Ok(&CLEAR_OPERATOR),
-            s => Err(PascalineError::OperatorSymbolError(s)),
+    /// Finds the built-in operator matching a symbol
+    pub fn from_symbol(symbol: &str) -> Result<Operator, PascalineError> {
+        BUILTINS.iter()
+            .find(|(_, m, _)| m.symbol == symbol)
+            .map(|(o, _, _)| *o)
+            .ok_or_else(|| PascalineError::OperatorSymbolError(symbol.to_string()))
+    }
+
+    /// Documentation of the operator
+    pub fn metadata(&self) -> Metadata {
+        match self {
+            Operator::Custom(op) => Metadata {
+                symbol: op.symbol(),
+                arity: op.arity(),
+                category: Category::Custom,
+                description: op.description(),
+                effect: "",
+                examples: &[]
+            },
+            _ => self.builtin().1
         }
     }
 
+    fn builtin(&self) -> &'static (Operator, Metadata, Apply) {
+        // Safe to unwrap, every built-in operator is listed
+        BUILTINS.iter().find(|(o, _, _)| o == self).unwrap()
+    }
+
+    /// Number of operands taken from the stack
     pub fn arity(&self) -> usize {
         match self {
-            Operator::Add => 2,
-            Operator::Sub => 2,
-            Operator::Mul => 2,
-            Operator::Div => 2,
-            Operator::EucDiv => 2,
-            Operator::Mod => 2,
-            Operator::Pow => 2,
-            Operator::Neg => 1,
-            Operator::Sin => 1,
-            Operator::Cos => 1,
-            Operator::Tan => 1,
-            Operator::ArcSin => 1,
-            Operator::ArcCos => 1,
-            Operator::ArcTan => 1,
-            Operator::Sqrt => 1,
-            Operator::Exp => 1,
-            Operator::Ln => 1,
-            // Operator::Num => 1,
-            // Operator::Den => 1,
-            // Operator::Complex => 2,
-            // Operator::Re => 1,
-            // Operator::Im => 1,
-            // Operator::Arg => 1,
-            // Operator::Norm => 1,
-            Operator::Eq => 2,
-            Operator::Neq => 2,
-            Operator::Le => 2,
-            Operator::Lt => 2,
-            Operator::Ge => 2,
-            Operator::Gt => 2,
-            Operator::And => 2,
-            Operator::Or => 2,
-            Operator::Not => 1,
-            Operator::Dup => 0,
-            Operator::Drop => 0,
-            Operator::Swap => 0,
-            Operator::LastOp => 0,
-            Operator::LastArgs => 0,
-            Operator::Undo => 0,
-            Operator::Redo => 0,
-            Operator::Clear => 0
-        }
-    }
-
-    pub fn symbol(&self) -> &str {
+            Operator::Custom(op) => op.arity(),
+            _ => self.metadata().arity
+        }
+    }
+
+    /// Symbol of the operator, as written in texts
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Custom(op) => op.symbol(),
+            _ => self.metadata().symbol
+        }
+    }
+
+    /// Applies the operator, raising errors for values out of its domain or range
+    pub fn operate(&self, operands: &[Token]) -> Result<Token, PascalineError> {
+        // Check the domain before computing, and the range of the result afterwards,
+        // so NaN and infinite values never end up in the stack
+        self.check_operands(operands)?;
+        self.check_domain(operands)?;
+
+        let result = self.operate_ieee(operands)?;
+
+        self.check_result(result)
+    }
+
+    /// Applies the operator with angles in degrees, which trigonometric functions take and their inverses give
+    ///
+    /// Other operators are applied as usual, following IEEE 754 semantics if `ieee` is set.
+    pub fn operate_degrees(&self, operands: &[Token], ieee: bool) -> Result<Token, PascalineError> {
+        if !self.is_angle_operator() {
+            return if ieee { self.operate_ieee(operands) } else { self.operate(operands) };
+        }
+
+        self.check_operands(operands)?;
+
+        if !ieee {
+            self.check_domain(operands)?;
+        }
+
+        // Computed with doubles, so results such as 45 degrees are not off by the rounding of pi
+        let x = || f64::from(Operator::unpack_one_float(operands));
+        let value = match self {
+            Operator::Sin => sin_cos_degrees(x()).0,
+            Operator::Cos => sin_cos_degrees(x()).1,
+            Operator::Tan => {
+                let (sin, cos) = sin_cos_degrees(x());
+
+                sin / cos
+            },
+            Operator::ArcSin => x().asin().to_degrees(),
+            Operator::ArcCos => x().acos().to_degrees(),
+            Operator::ArcTan => x().atan().to_degrees(),
+            _ => {
+                let (y, x) = Operator::unpack_two_floats(operands);
+
+                f64::from(y).atan2(f64::from(x)).to_degrees()
+            }
+        };
+        let result = Token::new_float(value as Float);
+
+        if ieee {
+            Ok(result)
+        } else {
+            self.check_result(result)
+        }
+    }
+
+    fn check_result(&self, result: Token) -> Result<Token, PascalineError> {
+        match result.as_float() {
+            Some(f) if f.is_nan() => Err(PascalineError::DomainError { op: self.symbol().to_string() }),
+            Some(f) if f.is_infinite() => Err(PascalineError::OverflowError { op: self.symbol().to_string() }),
+            _ => Ok(result)
+        }
+    }
+
+    /// Applies the operator, following IEEE 754 semantics
+    pub fn operate_ieee(&self, operands: &[Token]) -> Result<Token, PascalineError> {
+        // Follow IEEE 754 semantics : invalid operations and overflows
+        // respectively result in NaN and infinite values instead of errors
+        self.check_operands(operands)?;
+
+        // Values with a unit go through their own path, so units are never lost on the way
+        if operands.iter().any(Token::is_quantity) {
+            return self.operate_units(operands);
+        }
+
         match self {
-            Operator::Add => ADD,
-            Operator::Sub => SUB,
-            Operator::Mul => MUL,
-            Operator::Div => DIV,
-            Operator::EucDiv => EUC_DIV,
-            Operator::Mod => MOD,
-            Operator::Pow => POW,
-            Operator::Neg => NEG,
-            Operator::Sin => SIN,
-            Operator::Cos => COS,
-            Operator::Tan => TAN,
-            Operator::ArcSin => ARCSIN,
-            Operator::ArcCos => ARCCOS,
-            Operator::ArcTan => ARCTAN,
-            Operator::Sqrt => SQRT,
-            Operator::Exp => EXP,
-            Operator::Ln => LN,
-            // Operator::Num => NUM,
-            // Operator::Den => DEN,
-            // Operator::Complex => COMPLEX,
-            // Operator::Re => RE,
-            // Operator::Im => IM,
-            // Operator::Arg => ARG,
-            // Operator::Norm => NORM,
-            Operator::Eq => EQ,
-            Operator::Neq => NEQ,
-            Operator::Le => LE,
-            Operator::Lt => LT,
-            Operator::Ge => GE,
-            Operator::Gt => GT,
-            Operator::And => AND,
-            Operator::Or => OR,
-            Operator::Not => NOT,
-            Operator::Dup => DUP,
-            Operator::Drop => DROP,
-            Operator::Swap => SWAP,
-            Operator::LastOp => LASTOP,
-            Operator::LastArgs => LASTARGS,
-            Operator::Undo => UNDO,
-            Operator::Redo => REDO,
-            Operator::Clear => CLEAR
-        }
-    }
-
-    pub fn operate(&self, operands: &Vec<Token>) -> Result<Token, PascalineError> {
+            Operator::Custom(op) => op.apply(operands),
+            _ => (self.builtin().2)(operands)
+        }
+    }
+
+    fn check_operands(&self, operands: &[Token]) -> Result<(), PascalineError> {
         let nb_operands = operands.len();
         let arity = self.arity();
 
         // First, check we can unpack enough numbers
         if (arity > 0) && (nb_operands != arity) {
             Err(PascalineError::ArityError {
-                op: self.symbol(),
+                op: self.symbol().to_string(),
                 expected: arity,
                 found: nb_operands
             })
         // Then, check we all got numbers
-        } else if (arity > 0) && !Operator::are_valid(operands) {
+        } else if (arity > 0) && !self.are_valid(operands) {
             Err(PascalineError::TypeError)
-        // Finally, proceed
         } else {
-            match self {
-                Operator::Add => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_float(op1 + op2))
-                },
-                Operator::Sub => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_float(op1 - op2))
-                },
-                Operator::Mul => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_float(op1 * op2))
-                },
-                Operator::Div => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    if op2 == 0.0 {
-                        Err(PascalineError::ZeroDivisionError)
-                    } else {
-                        Ok(Token::new_float(op1 / op2))
-                    }
-                },
-                Operator::EucDiv => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_float(op1.div_euclid(op2)))
-                },
-                Operator::Mod => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_float(op1.rem_euclid(op2)))
-                },
-                Operator::Pow => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_float(op1.powf(op2)))
-                },
-                Operator::Neg => {
-                    let op = Operator::unpack_one_float(operands);
-
-                    Ok(Token::new_float(-op))
-                },
-                Operator::Sin => {
-                    let op = Operator::unpack_one_float(operands);
-
-                    Ok(Token::new_float(op.sin()))
-                },
-                Operator::Cos => {
-                    let op = Operator::unpack_one_float(operands);
-
-                    Ok(Token::new_float(op.cos()))
-                },
-                Operator::Tan => {
-                    let op = Operator::unpack_one_float(operands);
-
-                    Ok(Token::new_float(op.tan()))
-                },
-                Operator::ArcSin => {
-                    let op = Operator::unpack_one_float(operands);
-
-                    Ok(Token::new_float(op.asin()))
-                },
-                Operator::ArcCos => {
-                    let op = Operator::unpack_one_float(operands);
-
-                    Ok(Token::new_float(op.acos()))
-                },
-                Operator::ArcTan => {
-                    let op = Operator::unpack_one_float(operands);
-
-                    Ok(Token::new_float(op.atan()))
-                },
-                Operator::Sqrt => {
-                    let op = Operator::unpack_one_float(operands);
-
-                    Ok(Token::new_float(op.sqrt()))
-                },
-                Operator::Exp => {
-                    let op = Operator::unpack_one_float(operands);
-
-                    Ok(Token::new_float(op.exp()))
-                },
-                Operator::Ln => {
-                    let op = Operator::unpack_one_float(operands);
-
-                    Ok(Token::new_float(op.ln()))
-                },
-                // Operator::Num => Err(PascalineError::NotImplementedError),
-                // Operator::Den => Err(PascalineError::NotImplementedError),
-                // Operator::Complex => Err(PascalineError::NotImplementedError),
-                // Operator::Re => Err(PascalineError::NotImplementedError),
-                // Operator::Im => Err(PascalineError::NotImplementedError),
-                // Operator::Arg => Err(PascalineError::NotImplementedError),
-                // Operator::Norm => Err(PascalineError::NotImplementedError),
-                Operator::Eq => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_bool(op1 == op2))
-                },
-                Operator::Neq => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_bool(op1 != op2))
-                },
-                Operator::Le => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_bool(op1 <= op2))
-                },
-                Operator::Lt => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_bool(op1 < op2))
-                },
-                Operator::Ge => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_bool(op1 >= op2))
-                },
-                Operator::Gt => {
-                    let (op1, op2) = Operator::unpack_two_floats(operands);
-
-                    Ok(Token::new_bool(op1 > op2))
-                },
-                Operator::And => {
-                    let (op1, op2) = Operator::unpack_two_bools(operands);
-
-                    Ok(Token::new_bool(op1 && op2))
-                },
-                Operator::Or => {
-                    let (op1, op2) = Operator::unpack_two_bools(operands);
-
-                    Ok(Token::new_bool(op1 || op2))
-                },
-                Operator::Not => {
-                    let op = Operator::unpack_one_bool(operands);
-
-                    Ok(Token::new_bool(!op))
-                },
-                _ => Err(PascalineError::NotImplementedError)
-            }
+            Ok(())
+        }
+    }
+
+    fn check_domain(&self, operands: &[Token]) -> Result<(), PascalineError> {
+        // Only called once the operands have been checked
+        let domain_error = || PascalineError::DomainError { op: self.symbol().to_string() };
+
+        match self {
+            Operator::Div | Operator::EucDiv | Operator::Mod => {
+                let (_, op2) = Operator::unpack_two_floats(operands);
+
+                if op2 == 0.0 {
+                    Err(PascalineError::ZeroDivisionError)
+                } else {
+                    Ok(())
+                }
+            },
+            Operator::Pow => {
+                let (op1, op2) = Operator::unpack_two_floats(operands);
+
+                if (op1 == 0.0) && (op2 < 0.0) {
+                    Err(PascalineError::ZeroDivisionError)
+                } else if (op1 < 0.0) && (op2.fract() != 0.0) {
+                    Err(domain_error())
+                } else {
+                    Ok(())
+                }
+            },
+            Operator::ArcSin | Operator::ArcCos => {
+                let op = Operator::unpack_one_float(operands);
+
+                if op.abs() > 1.0 {
+                    Err(domain_error())
+                } else {
+                    Ok(())
+                }
+            },
+            Operator::Sqrt => {
+                let op = Operator::unpack_one_float(operands);
+
+                if op < 0.0 {
+                    Err(domain_error())
+                } else {
+                    Ok(())
+                }
+            },
+            Operator::Ln | Operator::Log | Operator::Log2 => {
+                let op = Operator::unpack_one_float(operands);
+
+                if op <= 0.0 {
+                    Err(domain_error())
+                } else {
+                    Ok(())
+                }
+            },
+            Operator::LogB => {
+                let (op1, op2) = Operator::unpack_two_floats(operands);
+
+                if (op1 <= 0.0) || (op2 <= 0.0) || (op2 == 1.0) {
+                    Err(domain_error())
+                } else {
+                    Ok(())
+                }
+            },
+            Operator::Ln1P => {
+                let op = Operator::unpack_one_float(operands);
+
+                if op <= -1.0 {
+                    Err(domain_error())
+                } else {
+                    Ok(())
+                }
+            },
+            Operator::ArcCosh => {
+                let op = Operator::unpack_one_float(operands);
+
+                if op < 1.0 {
+                    Err(domain_error())
+                } else {
+                    Ok(())
+                }
+            },
+            Operator::ArcTanh => {
+                let op = Operator::unpack_one_float(operands);
+
+                if op.abs() >= 1.0 {
+                    Err(domain_error())
+                } else {
+                    Ok(())
+                }
+            },
+            Operator::XRoot => {
+                let (op1, op2) = Operator::unpack_two_floats(operands);
+
+                if (op1 == 0.0) && (op2 < 0.0) {
+                    Err(PascalineError::ZeroDivisionError)
+                } else if (op2 == 0.0) || ((op1 < 0.0) && (op2.rem_euclid(2.0) != 1.0)) {
+                    Err(domain_error())
+                } else {
+                    Ok(())
+                }
+            },
+            Operator::Rnd => {
+                let (_, op2) = Operator::unpack_two_floats(operands);
+
+                if op2.fract() != 0.0 {
+                    Err(domain_error())
+                } else {
+                    Ok(())
+                }
+            },
+            // Other operators are either defined everywhere, or checked on their result
+            _ => Ok(())
         }
     }
 
-    fn are_valid(operands: &Vec<Token>) -> bool {
-        operands.iter().all(|t| t.is_number() || t.is_bool())
+    fn are_valid(&self, operands: &[Token]) -> bool {
+        // Only some operators know what to do with units
+        let units = self.is_unit_operator() || matches!(
+            self,
+            Operator::Add | Operator::Sub | Operator::Mul | Operator::Div | Operator::Neg |
+            Operator::Eq | Operator::Neq | Operator::Le | Operator::Lt | Operator::Ge | Operator::Gt
+        );
+
+        operands.iter().all(|t| t.is_number() || t.is_bool() || (units && t.is_quantity()))
+    }
+
+    fn is_angle_operator(&self) -> bool {
+        matches!(
+            self,
+            Operator::Sin | Operator::Cos | Operator::Tan | Operator::ArcSin | Operator::ArcCos | Operator::ArcTan | Operator::ArcTan2
+        )
+    }
+
+    fn is_unit_operator(&self) -> bool {
+        matches!(self, Operator::Convert | Operator::UBase | Operator::UVal)
     }
 
-    fn unpack_one_float(operands: &Vec<Token>) -> Float {
+    fn operate_units(&self, operands: &[Token]) -> Result<Token, PascalineError> {
+        // Only called once the operands have been checked, plain numbers having no unit
+        let value = |t: &Token| t.as_float().map_or(0.0, f64::from);
+        // Units which cannot be represented are reported as the operation giving them, written in RPN
+        let invalid = || PascalineError::UnitError(
+            operands.iter().map(|t| t.unit().to_string()).chain(Some(self.symbol().to_string())).collect::<Vec<String>>().join(" ")
+        );
+        let quantity = |v: f64, u: Unit| Token::new_quantity(v as Float, u);
+
+        match (self, operands) {
+            (Operator::Neg, [x]) => Ok(quantity(-value(x), x.unit())),
+            (Operator::UVal, [x]) => Ok(Token::new_float(value(x) as Float)),
+            (Operator::UBase, [x]) => x.unit().base()
+                .map(|b| quantity(value(x) * x.unit().scale(), b))
+                .ok_or_else(invalid),
+            (Operator::Mul, [x, y]) => x.unit().multiply(&y.unit())
+                .map(|u| quantity(value(x) * value(y), u))
+                .ok_or_else(invalid),
+            (Operator::Div, [x, y]) => x.unit().divide(&y.unit())
+                .map(|u| quantity(value(x) / value(y), u))
+                .ok_or_else(invalid),
+            (_, [x, y]) => {
+                let (left, right) = (x.unit(), y.unit());
+
+                // Sums, comparisons and conversions only make sense within the same dimension
+                if !left.is_compatible(&right) {
+                    return Err(PascalineError::DimensionError {
+                        left: left.to_string(),
+                        right: right.to_string()
+                    });
+                }
+
+                // The top value is expressed in the unit of the one below, except when converting
+                let (a, b) = (value(x), value(y) * right.scale() / left.scale());
+
+                match self {
+                    Operator::Add => Ok(quantity(a + b, left)),
+                    Operator::Sub => Ok(quantity(a - b, left)),
+                    Operator::Convert => Ok(quantity(a * left.scale() / right.scale(), right)),
+                    Operator::Eq => Ok(Token::new_bool(a == b)),
+                    Operator::Neq => Ok(Token::new_bool(a != b)),
+                    Operator::Le => Ok(Token::new_bool(a <= b)),
+                    Operator::Lt => Ok(Token::new_bool(a < b)),
+                    Operator::Ge => Ok(Token::new_bool(a >= b)),
+                    Operator::Gt => Ok(Token::new_bool(a > b)),
+                    _ => Err(PascalineError::TypeError)
+                }
+            },
+            _ => Err(PascalineError::TypeError)
+        }
+    }
+
+    fn unpack_one_float(operands: &[Token]) -> Float {
         // Only used once the size has been checked so it shouldn't be an issue
         operands.first().and_then(|t| t.as_float()).unwrap()
     }
 
-    fn unpack_one_bool(operands: &Vec<Token>) -> bool {
+    fn unpack_one_bool(operands: &[Token]) -> bool {
         // Only used once the size has been checked so it shouldn't be an issue
         operands.first().and_then(|t| t.as_bool()).unwrap()
     }
 
-    fn unpack_two_floats(operands: &Vec<Token>) -> (Float, Float) {
+    fn unpack_two_floats(operands: &[Token]) -> (Float, Float) {
         // Only used once the size has been checked so it shouldn't be an issue
         (
-            operands.get(0).and_then(|t| t.as_float()).unwrap(),
+            operands.first().and_then(|t| t.as_float()).unwrap(),
             operands.get(1).and_then(|t| t.as_float()).unwrap()
         )
     }
 
-    fn unpack_two_bools(operands: &Vec<Token>) -> (bool, bool) {
+    fn unpack_two_bools(operands: &[Token]) -> (bool, bool) {
         // Only used once the size has been checked so it shouldn't be an issue
         (
-            operands.get(0).and_then(|t| t.as_bool()).unwrap(),
+            operands.first().and_then(|t| t.as_bool()).unwrap(),
             operands.get(1).and_then(|t| t.as_bool()).unwrap()
         )
     }
 }
 
+// Computations of the built-in operators, which only see numbers and booleans
+
+fn add(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_float(op1 + op2))
+}
+
+fn sub(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_float(op1 - op2))
+}
+
+fn mul(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_float(op1 * op2))
+}
+
+fn div(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_float(op1 / op2))
+}
+
+fn euc_div(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_float(op1.div_euclid(op2)))
+}
+
+fn modulo(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_float(op1.rem_euclid(op2)))
+}
+
+fn pow(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_float(op1.powf(op2)))
+}
+
+fn neg(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(-op))
+}
+
+// Sine and cosine of an angle in degrees, exact for multiples of 90 degrees
+fn sin_cos_degrees(x: f64) -> (f64, f64) {
+    let angle = x.rem_euclid(360.0);
+
+    if angle % 90.0 == 0.0 {
+        match (angle / 90.0) as u8 {
+            0 => (0.0, 1.0),
+            1 => (1.0, 0.0),
+            2 => (0.0, -1.0),
+            _ => (-1.0, 0.0)
+        }
+    } else {
+        angle.to_radians().sin_cos()
+    }
+}
+
+fn sin(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.sin()))
+}
+
+fn cos(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.cos()))
+}
+
+fn tan(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.tan()))
+}
+
+fn arc_sin(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.asin()))
+}
+
+fn arc_cos(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.acos()))
+}
+
+fn arc_tan(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.atan()))
+}
+
+fn sinh(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.sinh()))
+}
+
+fn cosh(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.cosh()))
+}
+
+fn tanh(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.tanh()))
+}
+
+fn arc_sinh(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.asinh()))
+}
+
+fn arc_cosh(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.acosh()))
+}
+
+fn arc_tanh(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.atanh()))
+}
+
+fn arc_tan2(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_float(op1.atan2(op2)))
+}
+
+fn hypot(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_float(op1.hypot(op2)))
+}
+
+fn sqrt(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.sqrt()))
+}
+
+fn exp(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.exp()))
+}
+
+fn ln(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.ln()))
+}
+
+fn log(operands: &[Token]) -> Result<Token, PascalineError> {
+    // Computed on doubles, so exact results such as 1000 LOG are not lost to rounding
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(f64::from(op).log10() as Float))
+}
+
+fn log2(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(f64::from(op).log2() as Float))
+}
+
+fn logb(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_float((f64::from(op1).ln() / f64::from(op2).ln()) as Float))
+}
+
+fn alog(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(10f64.powf(f64::from(op)) as Float))
+}
+
+fn expm1(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.exp_m1()))
+}
+
+fn ln1p(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.ln_1p()))
+}
+
+fn cbrt(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_float(operands);
+
+    Ok(Token::new_float(op.cbrt()))
+}
+
+fn xroot(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+    let (x, n) = (f64::from(op1), f64::from(op2));
+
+    // Odd integer roots of negative numbers are real, as with CBRT
+    if (x < 0.0) && (n.rem_euclid(2.0) == 1.0) {
+        Ok(Token::new_float(-(-x).powf(n.recip()) as Float))
+    } else if n == 0.0 {
+        Ok(Token::new_float(Float::NAN))
+    } else {
+        Ok(Token::new_float(x.powf(n.recip()) as Float))
+    }
+}
+
+fn abs(operands: &[Token]) -> Result<Token, PascalineError> {
+    match operands {
+        // Integers stay exact, only the lowest one having no opposite
+        [Token::Integer(i)] => Ok(i.checked_abs().map_or_else(|| Token::new_float(-(*i as Float)), Token::new_integer)),
+        _ => Ok(Token::new_float(Operator::unpack_one_float(operands).abs()))
+    }
+}
+
+fn sign(operands: &[Token]) -> Result<Token, PascalineError> {
+    match operands {
+        [Token::Integer(i)] => Ok(Token::new_integer(i.signum())),
+        _ => {
+            let op = Operator::unpack_one_float(operands);
+
+            // Zero has no sign, whatever its bits say
+            if op == 0.0 {
+                Ok(Token::new_integer(0))
+            } else {
+                Ok(Token::new_float(op.signum()))
+            }
+        }
+    }
+}
+
+fn frac(operands: &[Token]) -> Result<Token, PascalineError> {
+    match operands {
+        [Token::Integer(_)] => Ok(Token::new_integer(0)),
+        _ => Ok(Token::new_float(Operator::unpack_one_float(operands).fract()))
+    }
+}
+
+fn rnd(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    // Integers have no decimals, and floats hold less than 9 significant digits
+    if op2.is_nan() {
+        Ok(Token::new_float(op2))
+    } else if matches!(operands, [Token::Integer(_), _]) && (op2 >= 0.0) {
+        Ok(operands[0])
+    } else if op2 >= 9.0 {
+        Ok(Token::new_float(op1))
+    } else {
+        let scale = 10f64.powi(op2.max(-39.0) as i32);
+
+        Ok(Token::new_float(((f64::from(op1) * scale).round() / scale) as Float))
+    }
+}
+
+fn eq(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_bool(op1 == op2))
+}
+
+fn neq(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_bool(op1 != op2))
+}
+
+fn le(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_bool(op1 <= op2))
+}
+
+fn lt(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_bool(op1 < op2))
+}
+
+fn ge(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_bool(op1 >= op2))
+}
+
+fn gt(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_floats(operands);
+
+    Ok(Token::new_bool(op1 > op2))
+}
+
+fn and(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_bools(operands);
+
+    Ok(Token::new_bool(op1 && op2))
+}
+
+fn or(operands: &[Token]) -> Result<Token, PascalineError> {
+    let (op1, op2) = Operator::unpack_two_bools(operands);
+
+    Ok(Token::new_bool(op1 || op2))
+}
+
+fn not(operands: &[Token]) -> Result<Token, PascalineError> {
+    let op = Operator::unpack_one_bool(operands);
+
+    Ok(Token::new_bool(!op))
+}
+
+fn round_with(operands: &[Token], round: fn(Float) -> Float) -> Result<Token, PascalineError> {
+    match operands {
+        [Token::Integer(_)] => Ok(operands[0]),
+        // Integral results become integers, unless they are too large for one
+        _ => Ok(Token::new_float(round(Operator::unpack_one_float(operands))))
+    }
+}
+
+fn floor(operands: &[Token]) -> Result<Token, PascalineError> {
+    round_with(operands, Float::floor)
+}
+
+fn ceil(operands: &[Token]) -> Result<Token, PascalineError> {
+    round_with(operands, Float::ceil)
+}
+
+fn round(operands: &[Token]) -> Result<Token, PascalineError> {
+    round_with(operands, Float::round)
+}
+
+fn trunc(operands: &[Token]) -> Result<Token, PascalineError> {
+    round_with(operands, Float::trunc)
+}
+
+fn pick(operands: &[Token], lowest: bool) -> Result<Token, PascalineError> {
+    match operands {
+        [Token::Integer(a), Token::Integer(b)] => Ok(Token::new_integer(if lowest { *a.min(b) } else { *a.max(b) })),
+        _ => {
+            let (op1, op2) = Operator::unpack_two_floats(operands);
+
+            // NaN is kept, rather than silently picking the other number
+            if op1.is_nan() || op2.is_nan() {
+                Ok(Token::new_float(Float::NAN))
+            } else if lowest {
+                Ok(Token::new_float(op1.min(op2)))
+            } else {
+                Ok(Token::new_float(op1.max(op2)))
+            }
+        }
+    }
+}
+
+fn min(operands: &[Token]) -> Result<Token, PascalineError> {
+    pick(operands, true)
+}
+
+fn max(operands: &[Token]) -> Result<Token, PascalineError> {
+    pick(operands, false)
+}
+
+fn convert(operands: &[Token]) -> Result<Token, PascalineError> {
+    Operator::Convert.operate_units(operands)
+}
+
+fn ubase(operands: &[Token]) -> Result<Token, PascalineError> {
+    Operator::UBase.operate_units(operands)
+}
+
+fn uval(operands: &[Token]) -> Result<Token, PascalineError> {
+    Operator::UVal.operate_units(operands)
+}
+
+// Stack operators work on the whole stack, which applies them itself
+fn stack_only(_: &[Token]) -> Result<Token, PascalineError> {
+    Err(PascalineError::NotImplementedError)
+}
+
 impl fmt::Display for Operator {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         write!(formatter, "{}", self.symbol())
     }
 }
 
+// Operators are written as their symbol
+#[cfg(feature = "serde")]
+impl Serialize for Operator {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.symbol())
+    }
+}
+
+// Only built-in operators can be read back, custom ones being unknown without a registry
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Operator {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Operator, D::Error> {
+        let symbol = String::deserialize(deserializer)?;
+
+        Operator::from_symbol(&symbol).map_err(de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::core::Operator;
-    use crate::core::symbols::ADD;
-    use std::ptr;
+    use crate::core::{ Operator, Token, Unit };
+    use crate::core::symbols::{
+        OPERATORS, ABS, ACOSH, ADD, ALOG, ARCSIN, ATAN2, ATANH, CBRT, CEIL, COSH, DIV, EXP, EXPM1, FLOOR, FRAC, HYPOT, IP,
+        LN, LN1P, LOG, LOG2, LOGB, MAX, MIN, MOD, MUL, POW, RND, ROUND, SIGN, SQRT, TAN, TANH, TRUNC, XROOT
+    };
+    use crate::errors::PascalineError;
+
+    fn apply(symbol: &str, operands: &[Token]) -> Result<Token, PascalineError> {
+        Operator::from_symbol(symbol).unwrap().operate(operands)
+    }
+
+    fn apply_ieee(symbol: &str, operands: &[Token]) -> Result<Token, PascalineError> {
+        Operator::from_symbol(symbol).unwrap().operate_ieee(operands)
+    }
+
+    #[test]
+    fn test_from_symbol() {
+        assert_eq!(Operator::from_symbol(ADD).unwrap(), Operator::Add);
+        assert_eq!(Operator::from_symbol(ADD).unwrap(), Operator::from_symbol(ADD).unwrap());
+        assert!(Operator::from_symbol("?").is_err());
+    }
+
+    #[test]
+    fn test_symbols_round_trip() {
+        for symbol in OPERATORS {
+            if let Ok(op) = Operator::from_symbol(symbol) {
+                assert_eq!(op.symbol(), *symbol);
+            }
+        }
+    }
+
+    #[test]
+    fn test_domain_errors() {
+        let cases = vec![
+            (SQRT, vec![Token::new_integer(-1)]),
+            (LN, vec![Token::new_integer(0)]),
+            (LN, vec![Token::new_integer(-2)]),
+            (ARCSIN, vec![Token::new_integer(2)]),
+            (POW, vec![Token::new_integer(-8), Token::new_float(0.5)]),
+            (LOG, vec![Token::new_integer(0)]),
+            (LOG2, vec![Token::new_integer(-1)]),
+            (LOGB, vec![Token::new_integer(8), Token::new_integer(1)]),
+            (LOGB, vec![Token::new_integer(8), Token::new_integer(-2)]),
+            (LN1P, vec![Token::new_integer(-1)]),
+            (ACOSH, vec![Token::new_float(0.5)]),
+            (ATANH, vec![Token::new_integer(1)]),
+            (XROOT, vec![Token::new_integer(-16), Token::new_integer(4)]),
+            (XROOT, vec![Token::new_integer(-8), Token::new_float(2.5)]),
+            (XROOT, vec![Token::new_integer(8), Token::new_integer(0)])
+        ];
+
+        for (symbol, operands) in cases {
+            match apply(symbol, &operands) {
+                Err(PascalineError::DomainError { op }) => assert_eq!(op, symbol),
+                r => panic!("Unexpected result for '{}' : {:?}", symbol, r)
+            }
+        }
+    }
+
+    #[test]
+    fn test_zero_division_errors() {
+        for symbol in &[DIV, MOD] {
+            match apply(symbol, &[Token::new_integer(1), Token::new_integer(0)]) {
+                Err(PascalineError::ZeroDivisionError) => (),
+                r => panic!("Unexpected result for '{}' : {:?}", symbol, r)
+            }
+        }
+    }
+
+    #[test]
+    fn test_overflow_errors() {
+        match apply(EXP, &[Token::new_integer(1000)]) {
+            Err(PascalineError::OverflowError { op }) => assert_eq!(op, EXP),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match apply(MUL, &[Token::new_float(1e30), Token::new_float(1e30)]) {
+            Err(PascalineError::OverflowError { op }) => assert_eq!(op, MUL),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_unit_errors() {
+        let quantity = |u: &str| Token::new_quantity(1.0, Unit::parse(u).unwrap());
+
+        // Units out of range are not an overflow of the values
+        match apply(MUL, &[quantity("m^127"), quantity("m")]) {
+            Err(PascalineError::UnitError(u)) => assert_eq!(u, "m^127 m *"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match apply(DIV, &[quantity("m^-127"), quantity("m")]) {
+            Err(PascalineError::UnitError(u)) => assert_eq!(u, "1/m^127 m /"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_valid_domain() {
+        assert_eq!(apply(SQRT, &[Token::new_integer(4)]).unwrap(), Token::new_integer(2));
+        assert_eq!(apply(LN, &[Token::new_integer(1)]).unwrap(), Token::new_integer(0));
+        assert_eq!(apply(POW, &[Token::new_integer(-2), Token::new_integer(3)]).unwrap(), Token::new_integer(-8));
+    }
+
+    #[test]
+    fn test_transcendental() {
+        let cases = vec![
+            (LOG, vec![Token::new_integer(1000)], Token::new_integer(3)),
+            (LOG2, vec![Token::new_integer(1024)], Token::new_integer(10)),
+            (LOGB, vec![Token::new_integer(81), Token::new_integer(3)], Token::new_integer(4)),
+            (ALOG, vec![Token::new_integer(-2)], Token::new_float(0.01)),
+            (CBRT, vec![Token::new_integer(-27)], Token::new_integer(-3)),
+            (XROOT, vec![Token::new_integer(32), Token::new_integer(5)], Token::new_integer(2)),
+            (XROOT, vec![Token::new_integer(-8), Token::new_integer(3)], Token::new_integer(-2)),
+            (XROOT, vec![Token::new_integer(4), Token::new_integer(-2)], Token::new_float(0.5)),
+            (HYPOT, vec![Token::new_integer(3), Token::new_integer(4)], Token::new_integer(5)),
+            (ATAN2, vec![Token::new_integer(1), Token::new_integer(0)], Token::new_float(std::f32::consts::FRAC_PI_2)),
+            // Like C and IEEE 754, the origin gives 0 rather than a domain error
+            (ATAN2, vec![Token::new_integer(0), Token::new_integer(0)], Token::new_integer(0)),
+            (ATAN2, vec![Token::new_integer(0), Token::new_integer(-1)], Token::new_float(std::f32::consts::PI)),
+            (ACOSH, vec![Token::new_integer(1)], Token::new_integer(0)),
+            (TANH, vec![Token::new_integer(0)], Token::new_integer(0))
+        ];
+
+        for (symbol, operands, expected) in cases {
+            assert_eq!(apply(symbol, &operands).unwrap(), expected, "{:?} {}", operands, symbol);
+        }
+
+        // Small values keep their precision, where EXP and LN lose it
+        assert_eq!(apply(EXPM1, &[Token::new_float(1e-10)]).unwrap(), Token::new_float(1e-10));
+        assert_eq!(apply(LN1P, &[Token::new_float(1e-10)]).unwrap(), Token::new_float(1e-10));
+
+        match apply(COSH, &[Token::new_integer(1000)]) {
+            Err(PascalineError::OverflowError { op }) => assert_eq!(op, COSH),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match apply(XROOT, &[Token::new_integer(0), Token::new_integer(-2)]) {
+            Err(PascalineError::ZeroDivisionError) => (),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match apply_ieee(ATANH, &[Token::new_integer(1)]) {
+            Ok(Token::Float(f)) => assert!(f.is_infinite()),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
 
     #[test]
-    fn test_singletons() {
-        let op1 = Operator::from_symbol(ADD).unwrap();
-        let op2 = Operator::from_symbol(ADD).unwrap();
+    fn test_rounding() {
+        let cases = vec![
+            (FLOOR, -2.5, Token::new_integer(-3)),
+            (CEIL, -2.5, Token::new_integer(-2)),
+            (ROUND, -2.5, Token::new_integer(-3)),
+            (ROUND, 2.4, Token::new_integer(2)),
+            (TRUNC, -2.5, Token::new_integer(-2)),
+            (IP, 2.5, Token::new_integer(2)),
+            (FRAC, -2.5, Token::new_float(-0.5)),
+            (ABS, -2.5, Token::new_float(2.5)),
+            (SIGN, -2.5, Token::new_integer(-1)),
+            (SIGN, -0.0, Token::new_integer(0))
+        ];
 
-        assert!(ptr::eq(op1, op2));
+        for (symbol, value, expected) in cases {
+            assert_eq!(apply(symbol, &[Token::new_float(value)]).unwrap(), expected, "{} {}", value, symbol);
+        }
+
+        // Integers stay exact, even beyond the precision of floats
+        assert_eq!(apply(FLOOR, &[Token::new_integer(16_777_217)]).unwrap(), Token::new_integer(16_777_217));
+        assert_eq!(apply(ABS, &[Token::new_integer(i32::MIN)]).unwrap(), Token::new_float(2_147_483_648.0));
+        assert_eq!(apply(MIN, &[Token::new_integer(2), Token::new_float(-3.5)]).unwrap(), Token::new_float(-3.5));
+        assert_eq!(apply(MAX, &[Token::new_integer(2), Token::new_integer(-3)]).unwrap(), Token::new_integer(2));
+
+        // Floats too large for an integer stay floats
+        assert_eq!(apply(FLOOR, &[Token::new_float(1e20)]).unwrap(), Token::new_float(1e20));
+
+        assert_eq!(apply(RND, &[Token::new_float(1.23456), Token::new_integer(2)]).unwrap(), Token::new_float(1.23));
+        assert_eq!(apply(RND, &[Token::new_float(-2.5), Token::new_integer(0)]).unwrap(), Token::new_integer(-3));
+        assert_eq!(apply(RND, &[Token::new_integer(1250), Token::new_integer(-2)]).unwrap(), Token::new_integer(1300));
+
+        match apply(RND, &[Token::new_float(1.23456), Token::new_float(1.5)]) {
+            Err(PascalineError::DomainError { op }) => assert_eq!(op, RND),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_units() {
+        let metre = Unit::parse("m").unwrap();
+        let quantity = |v, u| Token::new_quantity(v, Unit::parse(u).unwrap());
+
+        assert_eq!(Operator::Add.operate(&[quantity(1.0, "m"), quantity(50.0, "cm")]).unwrap(), quantity(1.5, "m"));
+        assert_eq!(Operator::Div.operate(&[quantity(10.0, "m"), quantity(2.0, "s")]).unwrap(), quantity(5.0, "m/s"));
+        assert_eq!(Operator::Mul.operate(&[quantity(2.0, "m"), Token::new_integer(3)]).unwrap(), quantity(6.0, "m"));
+        assert_eq!(Operator::Div.operate(&[quantity(4.0, "m"), quantity(2.0, "m")]).unwrap(), Token::new_integer(2));
+        assert_eq!(Operator::Convert.operate(&[quantity(1.0, "in"), quantity(1.0, "mm")]).unwrap(), quantity(25.4, "mm"));
+        assert_eq!(Operator::UBase.operate(&[quantity(2.0, "km")]).unwrap(), Token::new_quantity(2000.0, metre));
+        assert_eq!(Operator::UVal.operate(&[quantity(2.0, "km")]).unwrap(), Token::new_integer(2));
+        assert_eq!(Operator::Lt.operate(&[quantity(1.0, "ft"), quantity(1.0, "m")]).unwrap(), Token::new_bool(true));
+
+        match Operator::Sub.operate(&[quantity(1.0, "m"), Token::new_integer(1)]) {
+            Err(PascalineError::DimensionError { left, right }) => assert_eq!((left.as_str(), right.as_str()), ("m", "1")),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match Operator::Sqrt.operate(&[quantity(4.0, "m^2")]) {
+            Err(PascalineError::TypeError) => (),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match Operator::Div.operate(&[quantity(1.0, "m"), quantity(0.0, "s")]) {
+            Err(PascalineError::ZeroDivisionError) => (),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_ieee() {
+        match apply_ieee(SQRT, &[Token::new_integer(-1)]) {
+            Ok(Token::Float(f)) => assert!(f.is_nan()),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match apply_ieee(DIV, &[Token::new_integer(1), Token::new_integer(0)]) {
+            Ok(Token::Float(f)) => assert!(f.is_infinite() && f.is_sign_positive()),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match apply_ieee(LN, &[Token::new_integer(0)]) {
+            Ok(Token::Float(f)) => assert!(f.is_infinite() && f.is_sign_negative()),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_degrees() {
+        let degrees = |op: Operator, operands: &[Token]| op.operate_degrees(operands, false);
+
+        assert_eq!(degrees(Operator::Sin, &[Token::new_integer(90)]).unwrap(), Token::Integer(1));
+        assert_eq!(degrees(Operator::Sin, &[Token::new_integer(180)]).unwrap(), Token::Integer(0));
+        assert_eq!(degrees(Operator::Cos, &[Token::new_integer(-270)]).unwrap(), Token::Integer(0));
+        assert_eq!(degrees(Operator::ArcTan, &[Token::new_integer(1)]).unwrap(), Token::Integer(45));
+        assert_eq!(degrees(Operator::ArcTan2, &[Token::new_integer(1), Token::new_integer(0)]).unwrap(), Token::Integer(90));
+
+        match degrees(Operator::Sin, &[Token::new_integer(30)]) {
+            Ok(Token::Float(f)) => assert!((f - 0.5).abs() < 1e-6),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match degrees(Operator::Tan, &[Token::new_integer(90)]) {
+            Err(PascalineError::OverflowError { op }) => assert_eq!(op, TAN),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match degrees(Operator::ArcSin, &[Token::new_integer(2)]) {
+            Err(PascalineError::DomainError { op }) => assert_eq!(op, ARCSIN),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        // Other operators do not care about the angle mode
+        assert_eq!(degrees(Operator::Add, &[Token::new_integer(1), Token::new_integer(2)]).unwrap(), Token::Integer(3));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        assert_eq!(serde_json::to_string(&Operator::Pow).unwrap(), format!("\"{}\"", POW));
+        assert_eq!(serde_json::from_str::<Operator>("\"ARCSIN\"").unwrap(), Operator::ArcSin);
+        assert!(serde_json::from_str::<Operator>("\"NOPE\"").is_err());
+    }
+
+    #[test]
+    fn test_metadata() {
+        let c = &mut crate::calculator::Calculator::new();
+
+        for symbol in OPERATORS {
+            if let Ok(op) = Operator::from_symbol(symbol) {
+                let metadata = op.metadata();
+
+                assert_eq!(metadata.symbol, *symbol);
+                assert!(!metadata.description.is_empty());
+
+                // Examples must be valid, as they are shown to users
+                for example in metadata.examples {
+                    c.reset();
+                    assert!(c.eval(example).is_ok(), "Invalid example for {} : {}", symbol, example);
+                }
+            }
+        }
     }
 }
@@ -1,50 +1,259 @@
+use super::command::Command;
+use super::constant::Constant;
+use super::registry::Registry;
 use super::token::Token;
+use super::unit::Unit;
 use super::types::{ Int, Float };
-use super::symbols::{ FALSE, TRUE };
+use super::symbols::{ COMMANDS, COMMENT, FALSE, LEFT_PARENTHESIS, QUOTE, RIGHT_PARENTHESIS, TRUE };
 
+/// Location of a token in a text, as byte offsets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize
+}
+
+impl Span {
+    /// Column of the span in `text`, counted in characters and starting from 1
+    pub fn column(&self, text: &str) -> usize {
+        // Columns are counted in characters, starting from 1 like most editors do
+        text[..self.start].chars().count() + 1
+    }
+}
+
+/// Kind of a word, as found by the parser, so frontends can highlight texts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordKind {
+    Number,
+    Bool,
+    Operator,
+    Command,
+    // Word taken as argument by a command, such as a path
+    Argument,
+    Parenthesis,
+    // Parenthesis without a matching one
+    Unbalanced,
+    Comment,
+    // Word which is ignored when evaluated
+    Unknown
+}
+
+/// Splits texts into tokens
 #[derive(Debug)]
 pub struct Parser;
 
 impl Parser {
-    fn tokenize<'a>(&self, text: &'a str) -> std::str::SplitWhitespace<'a> {
+    fn find_comment(&self, text: &str) -> Option<usize> {
+        // Comment symbols between quotes belong to the quoted word
+        let mut quoted = false;
+
+        for (index, _) in text.char_indices() {
+            if text[index..].starts_with(QUOTE) {
+                quoted = !quoted;
+            } else if !quoted && text[index..].starts_with(COMMENT) {
+                return Some(index);
+            }
+        }
+
+        None
+    }
+
+    fn strip_comment<'a>(&self, text: &'a str) -> &'a str {
+        // Everything after the comment symbol is ignored until the end of the line
+        match self.find_comment(text) {
+            Some(index) => &text[..index],
+            None => text
+        }
+    }
+
+    fn tokenize<'a>(&self, text: &'a str) -> impl Iterator<Item = &'a str> {
         // TODO : Clever tokenization to handle operators split, etc.
-        text.split_whitespace()
+        self.tokenize_with_spans(text).into_iter().map(|(_, w)| w)
     }
 
-    fn make_token<'a>(&self, token: &'a str) -> Token<'a> {
+    pub(crate) fn tokenize_with_spans<'a>(&self, text: &'a str) -> Vec<(Span, &'a str)> {
+        // Words are split on whitespaces, except between quotes so arguments can hold spaces,
+        // and the location of each word in the text is kept so errors can point at it
+        let code = self.strip_comment(text);
+        let mut tokens = Vec::new();
+        let mut start = None;
+        let mut quoted = false;
+
+        for (index, c) in code.char_indices() {
+            if code[index..].starts_with(QUOTE) {
+                quoted = !quoted;
+            }
+
+            match (c.is_whitespace() && !quoted, start) {
+                (true, Some(s)) => {
+                    tokens.push((Span { start: s, end: index }, &code[s..index]));
+                    start = None;
+                },
+                (false, None) => start = Some(index),
+                _ => ()
+            }
+        }
+
+        if let Some(s) = start {
+            tokens.push((Span { start: s, end: code.len() }, &code[s..]));
+        }
+
+        tokens
+    }
+
+    pub(crate) fn unquote<'a>(&self, word: &'a str) -> &'a str {
+        // Quotes are only there to keep spaces in a word, and are not part of it
+        word.strip_prefix(QUOTE).and_then(|w| w.strip_suffix(QUOTE)).unwrap_or(word)
+    }
+
+    pub(crate) fn make_token(&self, token: &str, registry: &Registry) -> Token {
         // Try to parse as an int, or a float, or a token
         // If not possible, ignore
         // Token is assumed to be clean
+        // Only finite literals are numbers : INF and NAN are constants, and overflows are reported when evaluated
         match token.parse::<Int>() {
             Ok(i) => Token::new_integer(i),
             Err(_) => match token.parse::<Float>() {
-                Ok(f) => Token::new_float(f),
-                Err(_) => {
+                Ok(f) if f.is_finite() => Token::new_float(f),
+                _ => {
                     // Cache the value to avoid checking twice
                     let is_true = token == TRUE;
 
+                    // Constants come before operators, which cannot use their symbols anyway
                     if is_true || (token == FALSE) {
                         Token::new_bool(is_true)
+                    } else if let Some(q) = self.make_quantity(token) {
+                        q
+                    } else if let Some(c) = Constant::lookup(token) {
+                        c.token()
                     } else {
-                        Token::new_operator(token).unwrap_or(Token::new_ignored())
+                        registry.get(token).map(Token::Operator).unwrap_or_else(Token::new_ignored)
                     }
                 }
             },
         }
     }
 
-    pub fn parse<'a>(&self, text: &'a str) -> Vec<Token<'a>> {
+    fn make_quantity(&self, token: &str) -> Option<Token> {
+        let (value, unit) = self.split_quantity(token)?;
+
+        Some(Token::new_quantity(value, Unit::parse(unit)?))
+    }
+
+    fn split_quantity<'a>(&self, token: &'a str) -> Option<(Float, &'a str)> {
+        // Values with a unit are written as the value, an underscore and the unit, such as 9.81_m/s^2
+        // Physical constants start with an underscore, and are never taken for quantities
+        let (value, unit) = token.split_once('_')?;
+
+        if value.is_empty() {
+            return None;
+        }
+
+        Some((value.parse::<Float>().ok().filter(|f| f.is_finite())?, unit))
+    }
+
+    /// Value of a numeric literal too large for a float, which is not parsed as a number
+    pub(crate) fn overflowing_literal(&self, token: &str) -> Option<Float> {
+        // Words such as inf or NaN are read as floats too, but they are not literals
+        let numeric = token.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+
+        token.parse::<Float>().ok().filter(|f| numeric && f.is_infinite())
+    }
+
+    /// Unit of a word written as a quantity, when it is not a valid unit
+    pub(crate) fn unknown_unit<'a>(&self, token: &'a str) -> Option<&'a str> {
+        self.split_quantity(token).map(|(_, u)| u).filter(|u| Unit::parse(u).is_none())
+    }
+
+    /// Finds the kind of each word of a text, including comments
+    ///
+    /// Words are classified the same way they are parsed, so what is shown matches what is evaluated.
+    pub fn classify(&self, text: &str, registry: &Registry) -> Vec<(Span, WordKind)> {
+        let mut words = Vec::new();
+        let mut opened = Vec::new();
+        let mut argument = false;
+
+        for (span, word) in self.tokenize_with_spans(text) {
+            let command = Command::from_symbol(word);
+            let kind = if argument {
+                WordKind::Argument
+            } else if command.is_some() {
+                WordKind::Command
+            } else if word == LEFT_PARENTHESIS {
+                opened.push(words.len());
+                WordKind::Parenthesis
+            } else if word == RIGHT_PARENTHESIS {
+                match opened.pop() {
+                    Some(_) => WordKind::Parenthesis,
+                    None => WordKind::Unbalanced
+                }
+            } else {
+                match self.make_token(word, registry) {
+                    Token::Integer(_) | Token::Float(_) | Token::Quantity(_, _) => WordKind::Number,
+                    Token::Bool(_) => WordKind::Bool,
+                    Token::Operator(_) => WordKind::Operator,
+                    Token::Ignored => WordKind::Unknown
+                }
+            };
+
+            // Commands other than listings take the next word as argument
+            argument = (kind == WordKind::Command) && command.is_some_and(|c| c.takes_argument());
+            words.push((span, kind));
+        }
+
+        // Parentheses still opened at the end are not balanced either
+        for index in opened {
+            words[index].1 = WordKind::Unbalanced;
+        }
+
+        if let Some(index) = self.find_comment(text) {
+            words.push((Span { start: index, end: text.len() }, WordKind::Comment));
+        }
+
+        words
+    }
+
+    /// Symbols starting with `prefix` (whatever its case), sorted
+    pub fn complete<'r>(&self, prefix: &str, registry: &'r Registry) -> Vec<&'r str> {
+        let prefix = prefix.to_uppercase();
+        let mut symbols = registry.operators().iter()
+            .map(|o| o.symbol())
+            .chain(registry.aliases().map(|(a, _)| a))
+            .chain(COMMANDS.iter().copied())
+            .chain([TRUE, FALSE].iter().copied())
+            .chain(Constant::all().map(|c| c.symbol))
+            .filter(|s| s.to_uppercase().starts_with(&prefix))
+            .collect::<Vec<&str>>();
+
+        symbols.sort_unstable();
+        symbols.dedup();
+        symbols
+    }
+
+    /// Parses a text, skipping comments and unknown words
+    pub fn parse(&self, text: &str, registry: &Registry) -> Vec<Token> {
         // TODO : Force uppercase
         let tokens = self.tokenize(text);
 
-        tokens.map(|t| self.make_token(t)).filter(|t| t.is_legit()).collect::<Vec<Token>>()
+        tokens.map(|t| self.make_token(t, registry)).filter(|t| t.is_legit()).collect::<Vec<Token>>()
+    }
+
+    /// Same as [`Parser::parse`], along with the location of each token
+    pub fn parse_with_spans(&self, text: &str, registry: &Registry) -> Vec<(Span, Token)> {
+        // Spans are relative to the beginning of the text
+        self.tokenize_with_spans(text)
+            .into_iter()
+            .map(|(s, t)| (s, self.make_token(t, registry)))
+            .filter(|(_, t)| t.is_legit())
+            .collect::<Vec<(Span, Token)>>()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::core::{ Parser, Token };
-    use crate::core::symbols::{ ADD, SUB, MUL, DIV };
+    use crate::core::{ Parser, Registry, Span, Token, Unit, WordKind };
+    use crate::core::symbols::{ ADD, SUB, MUL, DIV, INF, NAN };
+    use crate::core::types::Float;
 
     #[test]
     fn test_tokenize_one_token() {
@@ -82,14 +291,14 @@ mod tests {
         let text = "";
         let tokens = p.tokenize(text).collect::<Vec<&str>>();
 
-        assert_eq!(tokens.is_empty(), true);
+        assert!(tokens.is_empty());
     }
 
     #[test]
     fn test_make_token_positive_int() {
         let p = Parser;
         let token = "42";
-        let parsed = p.make_token(token);
+        let parsed = p.make_token(token, &Registry::new());
         let expected = Token::new_integer(42);
 
         assert_eq!(parsed, expected);
@@ -99,7 +308,7 @@ mod tests {
     fn test_make_token_negative_int() {
         let p = Parser;
         let token = "-42";
-        let parsed = p.make_token(token);
+        let parsed = p.make_token(token, &Registry::new());
         let expected = Token::new_integer(-42);
 
         assert_eq!(parsed, expected);
@@ -109,7 +318,7 @@ mod tests {
     fn test_make_token_positive_float() {
         let p = Parser;
         let token = "13.37";
-        let parsed = p.make_token(token);
+        let parsed = p.make_token(token, &Registry::new());
         let expected = Token::new_float(13.37);
 
         assert_eq!(parsed, expected);
@@ -119,35 +328,51 @@ mod tests {
     fn test_make_token_negative_float() {
         let p = Parser;
         let token = "-13.37";
-        let parsed = p.make_token(token);
+        let parsed = p.make_token(token, &Registry::new());
         let expected = Token::new_float(-13.37);
 
         assert_eq!(parsed, expected);
     }
 
+    #[test]
+    fn test_make_token_non_finite() {
+        let p = Parser;
+
+        // Non-finite values only come from the constants, whatever the case
+        for token in &["inf", "nan", "NaN", "infinity", "-inf", "1e40", "inf_m"] {
+            assert_eq!(p.make_token(token, &Registry::new()), Token::new_ignored(), "{}", token);
+        }
+
+        assert!(p.make_token(INF, &Registry::new()).as_float().unwrap().is_infinite());
+        assert!(p.make_token(NAN, &Registry::new()).as_float().unwrap().is_nan());
+        assert_eq!(p.overflowing_literal("-1e40"), Some(Float::NEG_INFINITY));
+        assert_eq!(p.overflowing_literal("inf"), None);
+        assert_eq!(p.overflowing_literal("1e30"), None);
+    }
+
     #[test]
     fn test_make_token_operator() {
         let p = Parser;
         let mut token = "+";
-        let mut parsed = p.make_token(token);
+        let mut parsed = p.make_token(token, &Registry::new());
         let mut expected = Token::new_operator(ADD).unwrap();
 
         assert_eq!(parsed, expected);
 
         token = "-";
-        parsed = p.make_token(token);
+        parsed = p.make_token(token, &Registry::new());
         expected = Token::new_operator(SUB).unwrap();
 
         assert_eq!(parsed, expected);
 
         token = "*";
-        parsed = p.make_token(token);
+        parsed = p.make_token(token, &Registry::new());
         expected = Token::new_operator(MUL).unwrap();
 
         assert_eq!(parsed, expected);
 
         token = "/";
-        parsed = p.make_token(token);
+        parsed = p.make_token(token, &Registry::new());
         expected = Token::new_operator(DIV).unwrap();
 
         assert_eq!(parsed, expected);
@@ -157,7 +382,7 @@ mod tests {
     fn test_make_token_ignored() {
         let p = Parser;
         let token = "text";
-        let parsed = p.make_token(token);
+        let parsed = p.make_token(token, &Registry::new());
         let expected = Token::new_ignored();
 
         assert_eq!(parsed, expected);
@@ -167,7 +392,7 @@ mod tests {
     fn test_parse() {
         let p = Parser;
         let text = "1 + 2.0 - 5.5    text ";
-        let parsed = p.parse(text);
+        let parsed = p.parse(text, &Registry::new());
         let expected = vec![
             Token::new_integer(1),
             Token::new_operator(ADD).unwrap(),
@@ -178,4 +403,116 @@ mod tests {
 
         assert_eq!(parsed, expected);
     }
+
+    #[test]
+    fn test_tokenize_comment() {
+        let p = Parser;
+        let text = "1 2 + # 3 *";
+        let tokens = p.tokenize(text).collect::<Vec<&str>>();
+        let expected = vec!["1", "2", "+"];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_tokenize_comment_only() {
+        let p = Parser;
+        let text = "  # Nothing but a comment";
+        let tokens = p.tokenize(text).collect::<Vec<&str>>();
+
+        assert!(tokens.is_empty());
+    }
+
+    #[test]
+    fn test_tokenize_with_spans() {
+        let p = Parser;
+        let text = "  test of   tokenization # comment";
+        let tokens = p.tokenize_with_spans(text);
+        let expected = vec![
+            (Span { start: 2, end: 6 }, "test"),
+            (Span { start: 7, end: 9 }, "of"),
+            (Span { start: 12, end: 24 }, "tokenization")
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_parse_with_spans() {
+        let p = Parser;
+        let text = "1 text 2.5 +";
+        let parsed = p.parse_with_spans(text, &Registry::new());
+        let expected = vec![
+            (Span { start: 0, end: 1 }, Token::new_integer(1)),
+            (Span { start: 7, end: 10 }, Token::new_float(2.5)),
+            (Span { start: 11, end: 12 }, Token::new_operator(ADD).unwrap())
+        ];
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_span_column() {
+        let span = Span { start: 3, end: 4 };
+
+        assert_eq!(span.column("é 2 +"), 3);
+        assert_eq!(span.column("12 +"), 4);
+    }
+
+    #[test]
+    fn test_classify() {
+        let p = Parser;
+        let text = ") 1 TRUE + nope SAVE a.json ( # done";
+        let kinds = p.classify(text, &Registry::new()).into_iter().map(|(_, k)| k).collect::<Vec<WordKind>>();
+        let expected = vec![
+            WordKind::Unbalanced,
+            WordKind::Number,
+            WordKind::Bool,
+            WordKind::Operator,
+            WordKind::Unknown,
+            WordKind::Command,
+            WordKind::Argument,
+            WordKind::Unbalanced,
+            WordKind::Comment
+        ];
+
+        assert_eq!(kinds, expected);
+        assert_eq!(p.classify("( 1 )", &Registry::new())[2].1, WordKind::Parenthesis);
+
+        // Quoted arguments are single words, whatever they hold
+        let kinds = p.classify("SAVE \"my #1.json\" 2", &Registry::new());
+
+        assert_eq!(kinds, vec![
+            (Span { start: 0, end: 4 }, WordKind::Command),
+            (Span { start: 5, end: 17 }, WordKind::Argument),
+            (Span { start: 18, end: 19 }, WordKind::Number)
+        ]);
+    }
+
+    #[test]
+    fn test_complete() {
+        let p = Parser;
+
+        assert_eq!(p.complete("arc", &Registry::new()), vec!["ARCCOS", "ARCSIN", "ARCTAN"]);
+        assert_eq!(p.complete("TR", &Registry::new()), vec!["TRUE", "TRUNC"]);
+        assert_eq!(p.complete("p", &Registry::new()), vec!["PHI", "PI", "POW"]);
+        assert_eq!(p.classify("PI 2 *", &Registry::new())[1].1, WordKind::Number);
+    }
+
+    #[test]
+    fn test_quantities() {
+        let p = Parser;
+        let tokens = p.parse("9.81_m/s^2 2_furlong _c", &Registry::new());
+
+        // Unknown units are not parsed, and constants are not taken for quantities
+        assert_eq!(tokens, vec![
+            Token::new_quantity(9.81, Unit::parse("m/s^2").unwrap()),
+            Token::new_quantity(299_792_458.0, Unit::parse("m/s").unwrap())
+        ]);
+        assert_eq!(p.unknown_unit("2_furlong"), Some("furlong"));
+        assert_eq!(p.unknown_unit("2_m/s"), None);
+        assert_eq!(p.unknown_unit("_c"), None);
+        assert_eq!(p.unknown_unit("x_m"), None);
+        assert!(p.complete("NOPE", &Registry::new()).is_empty());
+    }
 }
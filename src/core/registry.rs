@@ -0,0 +1,286 @@
+use super::constant::Constant;
+use super::operator::Operator;
+use super::symbols::{ OPERATORS, RESERVED, COMMENT, QUOTE };
+use super::token::Token;
+use super::types::Float;
+use crate::errors::PascalineError;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Behavior of an operator, so other crates can provide their own
+///
+/// Operators are expected to live as long as the program (usually as a static),
+/// which keeps tokens cheap to copy and free of any lifetime.
+pub trait OperatorImpl: fmt::Debug + Send + Sync {
+    /// Symbol of the operator, as written in texts
+    fn symbol(&self) -> &'static str;
+
+    /// Number of operands taken from the stack
+    fn arity(&self) -> usize;
+
+    /// Computes the result of the operator
+    ///
+    /// Operands are given from the deepest to the top of the stack,
+    /// and have already been checked against the arity.
+    fn apply(&self, operands: &[Token]) -> Result<Token, PascalineError>;
+
+    /// One-line description, shown by `HELP`
+    fn description(&self) -> &'static str {
+        ""
+    }
+}
+
+// Symbols are unique, so they are enough to tell operators apart
+impl PartialEq for dyn OperatorImpl {
+    fn eq(&self, other: &dyn OperatorImpl) -> bool {
+        self.symbol() == other.symbol()
+    }
+}
+
+impl Eq for dyn OperatorImpl {}
+
+impl OperatorImpl for Operator {
+    fn symbol(&self) -> &'static str {
+        Operator::symbol(self)
+    }
+
+    fn arity(&self) -> usize {
+        Operator::arity(self)
+    }
+
+    fn apply(&self, operands: &[Token]) -> Result<Token, PascalineError> {
+        self.operate(operands)
+    }
+
+    fn description(&self) -> &'static str {
+        self.metadata().description
+    }
+}
+
+/// Operators known by a calculator, by symbol
+///
+/// Operators can also be known by aliases, which are only other names for them.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    operators: Vec<Operator>,
+    symbols: HashMap<String, Operator>
+}
+
+impl Registry {
+    /// Creates a registry with all the built-in operators
+    pub fn new() -> Registry {
+        let mut registry = Registry::empty();
+
+        // Built-in operators go through the same path as custom ones
+        // Symbols listed for operators which are not implemented yet are skipped
+        for op in OPERATORS.iter().filter_map(|s| Operator::from_symbol(s).ok()) {
+            // Safe to unwrap, built-in symbols are unique
+            registry.insert(op).unwrap();
+        }
+
+        registry
+    }
+
+    /// Creates a registry without any operator
+    pub fn empty() -> Registry {
+        Registry {
+            operators: Vec::new(),
+            symbols: HashMap::new()
+        }
+    }
+
+    /// Registers a custom operator, failing if its symbol is invalid or already in use
+    pub fn register(&mut self, op: &'static dyn OperatorImpl) -> Result<Operator, PascalineError> {
+        self.insert(Operator::Custom(op))
+    }
+
+    /// Gives another name to the operator using `symbol`, failing if either is invalid or the name already in use
+    pub fn alias(&mut self, alias: &str, symbol: &str) -> Result<Operator, PascalineError> {
+        let op = self.get(symbol).ok_or_else(|| PascalineError::OperatorSymbolError(symbol.to_string()))?;
+
+        self.check_symbol(alias)?;
+        self.symbols.insert(alias.to_string(), op);
+
+        Ok(op)
+    }
+
+    fn insert(&mut self, op: Operator) -> Result<Operator, PascalineError> {
+        let symbol = op.symbol();
+
+        self.check_symbol(symbol)?;
+        self.operators.push(op);
+        self.symbols.insert(symbol.to_string(), op);
+
+        Ok(op)
+    }
+
+    fn check_symbol(&self, symbol: &str) -> Result<(), PascalineError> {
+        // Symbols must be single words that cannot be mistaken for anything else
+        if symbol.is_empty()
+            || symbol.contains(char::is_whitespace)
+            || symbol.contains(COMMENT)
+            || symbol.contains(QUOTE)
+            || symbol.parse::<Float>().is_ok()
+        {
+            Err(PascalineError::OperatorSymbolError(symbol.to_string()))
+        } else if RESERVED.contains(&symbol) || Constant::lookup(symbol).is_some() || self.symbols.contains_key(symbol) {
+            Err(PascalineError::SymbolCollisionError(symbol.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Finds the operator matching a symbol
+    pub fn get(&self, symbol: &str) -> Option<Operator> {
+        self.symbols.get(symbol).copied()
+    }
+
+    /// Whether an operator uses this symbol
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.symbols.contains_key(symbol)
+    }
+
+    /// Aliases along with the symbol of their operator, in no particular order
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &'static str)> {
+        self.symbols.iter().map(|(s, o)| (s.as_str(), o.symbol())).filter(|(s, o)| s != o)
+    }
+
+    /// All the operators, in registration order
+    pub fn operators(&self) -> &[Operator] {
+        &self.operators
+    }
+
+    /// Number of operators
+    pub fn len(&self) -> usize {
+        self.operators.len()
+    }
+
+    /// Whether there is no operator at all
+    pub fn is_empty(&self) -> bool {
+        self.operators.is_empty()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        Registry::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{ Operator, OperatorImpl, Registry, Token };
+    use crate::core::symbols::{ ADD, TRUE };
+    use crate::errors::PascalineError;
+
+    #[derive(Debug)]
+    struct Double;
+
+    impl OperatorImpl for Double {
+        fn symbol(&self) -> &'static str {
+            "DOUBLE"
+        }
+
+        fn arity(&self) -> usize {
+            1
+        }
+
+        fn apply(&self, operands: &[Token]) -> Result<Token, PascalineError> {
+            let op = operands[0].as_float().ok_or(PascalineError::TypeError)?;
+
+            Ok(Token::new_float(op * 2.0))
+        }
+    }
+
+    #[derive(Debug)]
+    struct Named(&'static str);
+
+    impl OperatorImpl for Named {
+        fn symbol(&self) -> &'static str {
+            self.0
+        }
+
+        fn arity(&self) -> usize {
+            0
+        }
+
+        fn apply(&self, _: &[Token]) -> Result<Token, PascalineError> {
+            Err(PascalineError::NotImplementedError)
+        }
+    }
+
+    static DOUBLE: Double = Double;
+    static PLUS: Named = Named(ADD);
+    static YES: Named = Named(TRUE);
+    static SPACED: Named = Named("A B");
+    static NUMBER: Named = Named("42");
+
+    #[test]
+    fn test_builtins() {
+        let r = Registry::new();
+
+        assert_eq!(r.get(ADD), Some(Operator::Add));
+        assert!(r.get("DOUBLE").is_none());
+        assert!(Registry::empty().is_empty());
+    }
+
+    #[test]
+    fn test_register() {
+        let mut r = Registry::new();
+        let size = r.len();
+        let op = r.register(&DOUBLE).unwrap();
+
+        assert_eq!(r.len(), size + 1);
+        assert_eq!(r.get("DOUBLE"), Some(op));
+        assert_eq!(op.symbol(), "DOUBLE");
+        assert_eq!(op.operate(&[Token::new_integer(21)]), Ok(Token::new_integer(42)));
+    }
+
+    #[test]
+    fn test_register_collision() {
+        let mut r = Registry::new();
+
+        r.register(&DOUBLE).unwrap();
+
+        for op in &[&DOUBLE as &'static dyn OperatorImpl, &PLUS, &YES] {
+            match r.register(*op) {
+                Err(PascalineError::SymbolCollisionError(s)) => assert_eq!(s, op.symbol()),
+                e => panic!("Unexpected result : {:?}", e)
+            }
+        }
+    }
+
+    #[test]
+    fn test_alias() {
+        let mut r = Registry::new();
+        let size = r.len();
+
+        assert_eq!(r.alias("PLUS", ADD), Ok(Operator::Add));
+        assert_eq!(r.get("PLUS"), Some(Operator::Add));
+        assert_eq!(r.aliases().collect::<Vec<(&str, &str)>>(), vec![("PLUS", ADD)]);
+
+        // Aliases are other names, not new operators
+        assert_eq!(r.len(), size);
+
+        for (alias, symbol, error) in [
+            ("MORE", "NOPE", PascalineError::OperatorSymbolError(String::from("NOPE"))),
+            ("A B", ADD, PascalineError::OperatorSymbolError(String::from("A B"))),
+            ("PLUS", ADD, PascalineError::SymbolCollisionError(String::from("PLUS"))),
+            (TRUE, ADD, PascalineError::SymbolCollisionError(String::from(TRUE)))
+        ] {
+            assert_eq!(r.alias(alias, symbol), Err(error));
+        }
+    }
+
+    #[test]
+    fn test_register_invalid_symbol() {
+        let mut r = Registry::new();
+
+        for op in &[&SPACED as &'static dyn OperatorImpl, &NUMBER] {
+            match r.register(*op) {
+                Err(PascalineError::OperatorSymbolError(s)) => assert_eq!(s, op.symbol()),
+                e => panic!("Unexpected result : {:?}", e)
+            }
+        }
+    }
+}
@@ -1,139 +1,409 @@
 use super::token::Token;
 use super::operator::Operator;
 use crate::errors::PascalineError;
+#[cfg(feature = "serde")]
+use serde::{ de, Deserialize, Deserializer, Serialize, Serializer };
 use std::fmt;
 
 
-#[derive(Debug)]
-pub struct Stack<'a> {
-    stack: Vec<Token<'a>>,
-    last_op: Option<&'a Operator>
+/// Computation stack, implementing a RPN logic
+///
+/// With the `serde` feature, stacks are written as their values, last operator, modes and capacity.
+#[derive(Debug, Clone)]
+pub struct Stack {
+    stack: Vec<Token>,
+    last_op: Option<Operator>,
+    ieee: bool,
+    degrees: bool,
+    capacity: Option<usize>
 }
 
-const STACK_CAPACITY: usize = 4096;
+/// Number of values a stack can hold, unless told otherwise
+pub const DEFAULT_CAPACITY: usize = 4096;
 
-// Computation stack, implementing a RPN logic
-impl<'a> Stack<'a> {
-    pub fn new() -> Stack<'a> {
+// Internal signal telling what to do once an operator has been handled
+enum Flow {
+    // The operator has been applied and becomes the last operator
+    Done,
+    // The given operator has to be applied in turn
+    Replay(Operator)
+}
+
+impl Stack {
+    /// Creates an empty stack, holding up to [`DEFAULT_CAPACITY`] values
+    pub fn new() -> Stack {
+        Stack::with_limit(Some(DEFAULT_CAPACITY))
+    }
+
+    /// Creates an empty stack, holding up to `limit` values
+    pub fn with_capacity(limit: usize) -> Stack {
+        Stack::with_limit(Some(limit))
+    }
+
+    /// Creates an empty stack, holding as many values as the memory allows
+    pub fn unbounded() -> Stack {
+        Stack::with_limit(None)
+    }
+
+    fn with_limit(capacity: Option<usize>) -> Stack {
+        // Memory is only allocated as values are pushed
         Stack {
-            stack: Vec::with_capacity(STACK_CAPACITY),
-            last_op: None
+            stack: Vec::new(),
+            last_op: None,
+            ieee: false,
+            degrees: false,
+            capacity
         }
     }
 
+    /// Number of values the stack can hold, or `None` if unbounded
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Changes the number of values the stack can hold, `None` meaning unbounded
+    ///
+    /// Values beyond a lower limit are kept, but nothing more can be pushed until some are removed.
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+    }
+
+    pub(crate) fn check_capacity(&self, size: usize) -> Result<(), PascalineError> {
+        match self.capacity {
+            Some(c) if size > c => Err(PascalineError::FullStackError { capacity: c }),
+            _ => Ok(())
+        }
+    }
+
+    /// Whether invalid operations and overflows give NaN and infinite values instead of errors
+    pub fn is_ieee(&self) -> bool {
+        self.ieee
+    }
+
+    /// Enables or disables IEEE 754 semantics
+    pub fn set_ieee(&mut self, ieee: bool) {
+        // In IEEE mode, NaN and infinite values are pushed instead of raising errors
+        self.ieee = ieee;
+    }
+
+    /// Whether trigonometric functions take angles in degrees, and their inverses give them, instead of radians
+    pub fn is_degrees(&self) -> bool {
+        self.degrees
+    }
+
+    /// Switches angles to degrees, or back to radians
+    pub fn set_degrees(&mut self, degrees: bool) {
+        self.degrees = degrees;
+    }
+
+    /// Number of values in the stack
     pub fn size(&self) -> usize {
         self.stack.len()
     }
 
+    /// Values of the stack, from the deepest to the top
+    pub fn values(&self) -> &[Token] {
+        &self.stack
+    }
+
+    /// Last operator applied, if any
+    pub fn last_op(&self) -> Option<Operator> {
+        self.last_op
+    }
+
+    pub(crate) fn restore(&mut self, values: Vec<Token>, last_op: Option<Operator>) {
+        // Values are trusted to come from another stack, so the capacity is not checked
+        self.stack = values;
+        self.last_op = last_op;
+    }
+
+    /// Removes the value on top of the stack and returns it, if any
+    pub fn pop(&mut self) -> Option<Token> {
+        self.stack.pop()
+    }
+
+    /// Removes the value at `index` (0 being the deepest) and returns it, if any
+    pub fn remove(&mut self, index: usize) -> Option<Token> {
+        if index < self.stack.len() {
+            Some(self.stack.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the value at `index` (0 being the deepest)
+    pub fn replace(&mut self, index: usize, token: Token) -> Result<(), PascalineError> {
+        // Only values can be held by the stack, operators are applied instead
+        if !token.is_value() {
+            return Err(PascalineError::TypeError);
+        }
+
+        match self.stack.get_mut(index) {
+            Some(t) => {
+                *t = token;
+                Ok(())
+            },
+            None => Err(PascalineError::EmptyStackError)
+        }
+    }
+
+    /// Removes all the values
     pub fn clear(&mut self) {
         self.stack.clear()
     }
 
-    pub fn push(&mut self, token: Token<'a>) -> Result<(), PascalineError> {
-        let stack_size = self.stack.len();
+    /// Pushes a token, applying it right away if it is an operator
+    pub fn push(&mut self, token: Token) -> Result<(), PascalineError> {
+        // Operators taking operands never make the stack grow, so they still apply on a full stack
+        let grows = match token {
+            Token::Operator(Operator::Dup) => true,
+            Token::Operator(Operator::Drop) | Token::Operator(Operator::Swap) |
+            Token::Operator(Operator::Clear) | Token::Operator(Operator::LastOp) => false,
+            Token::Operator(op) => op.arity() == 0,
+            _ => true
+        };
 
         // If stack is full, throw error
-        if stack_size >= STACK_CAPACITY {
-            Err(PascalineError::FullStackError)
+        if let (true, Err(e)) = (grows, self.check_capacity(self.stack.len() + 1)) {
+            Err(e)
         } else {
             // If the token is an operator, then we need to pop some elements and run the operator
             // Results will be pushed to the stack
             match token {
                 Token::Operator(op) => {
-                    // First, check for stack operators
-                    let result = match op {
-                        Operator::Dup => {
-                            match self.stack.first() {
-                                Some(&t) => Ok(self.stack.push(t)),
-                                None => Err(PascalineError::EmptyStackError)
-                            }
-                        },
-                        Operator::Drop => {
-                            match self.stack.pop() {
-                                Some(_) => Ok(()),
-                                None => Err(PascalineError::EmptyStackError)
-                            }
-                        },
-                        Operator::Swap => {
-                            if stack_size < 2 {
-                                Err(PascalineError::ArityError {
-                                    op: op.symbol(),
-                                    expected: 2,
-                                    found: stack_size
-                                })
-                            } else {
-                                Ok(self.stack.swap(stack_size - 1, stack_size - 2))
-                            }
+                    let flow = match op {
+                        Operator::LastOp => match self.last_op {
+                            None => Err(PascalineError::NoLastOperatorError),
+                            Some(o) => Ok(Flow::Replay(o))
                         },
-                        Operator::Clear => Ok(self.clear()),
-                        Operator::LastOp => {
-                            // Just push the last op to the stack to handle all the logic
-                            match self.last_op {
-                                None => Err(PascalineError::NoLastOperatorError),
-                                Some(_) => Err(PascalineError::LastOperatorFlag)
-                                // Some(o) => self.push(Token::new_operator(o.symbol()).unwrap())
-                            }
-                        },
-                        // Otherwise, apply the operator's logic
-                        _ => {
-
-                            let arity = op.arity();
-
-                            if stack_size < arity {
-                                Err(PascalineError::ArityError {
-                                    op: op.symbol(),
-                                    expected: arity,
-                                    found: stack_size
-                                })
-                            } else {
-                                // Get the operands
-                                let operands = self.stack.split_off(stack_size - arity);
-
-                                // Try to run the operator
-                                match op.operate(&operands) {
-                                    // If it fails, recover the tokens in the stack
-                                    Err(e) => {
-                                        self.stack.extend(operands);
-                                        Err(e)
-                                    },
-                                    // Otherwise, push the result
-                                    Ok(t) => Ok(self.stack.push(t))
-                                }
-                            }
-                        }
+                        _ => self.apply(op).map(|_| Flow::Done)
                     };
 
                     // Check the result before storing the last operator
                     // If something went bad, just return right away
-                    // Otherwise, store the last op (if not the LASTOP op)
-                    // This a bit of a dirty workaround because I couldn't find any elegant
-                    // solution to avoid borrowing issues when running the recursion earlier
-                    match result {
-                        Err(PascalineError::LastOperatorFlag) => {
-                            // Safe to unwrap because the flag tells us we can apply this operation
-                            // Same for the created token, the operator is sure to be valid
-                            let last_op = self.last_op.unwrap();
-                            self.push(Token::new_operator(last_op.symbol()).unwrap())
-                        },
-                        Err(e) => Err(e),
-                        Ok(v) => {
+                    // Otherwise, store the last op (LASTOP replays the last op, which stores itself)
+                    match flow? {
+                        Flow::Replay(o) => self.push(Token::Operator(o)),
+                        Flow::Done => {
                             self.last_op = Some(op);
-                            Ok(v)
+                            Ok(())
                         }
                     }
                 },
                 Token::Ignored => Err(PascalineError::TypeError),
-                t => Ok(self.stack.push(t))
+                t => {
+                    self.stack.push(t);
+                    Ok(())
+                }
             }
         }
     }
 
+    fn apply(&mut self, op: Operator) -> Result<(), PascalineError> {
+        let stack_size = self.stack.len();
+
+        // First, check for stack operators
+        match op {
+            Operator::Dup => {
+                match self.stack.last() {
+                    Some(&t) => {
+                        self.stack.push(t);
+                        Ok(())
+                    },
+                    None => Err(PascalineError::EmptyStackError)
+                }
+            },
+            Operator::Drop => {
+                match self.stack.pop() {
+                    Some(_) => Ok(()),
+                    None => Err(PascalineError::EmptyStackError)
+                }
+            },
+            Operator::Swap => {
+                if stack_size < 2 {
+                    Err(PascalineError::ArityError {
+                        op: op.symbol().to_string(),
+                        expected: 2,
+                        found: stack_size
+                    })
+                } else {
+                    self.stack.swap(stack_size - 1, stack_size - 2);
+                    Ok(())
+                }
+            },
+            Operator::Clear => {
+                self.clear();
+                Ok(())
+            },
+            // Otherwise, apply the operator's logic
+            _ => {
+                let arity = op.arity();
+
+                if stack_size < arity {
+                    Err(PascalineError::ArityError {
+                        op: op.symbol().to_string(),
+                        expected: arity,
+                        found: stack_size
+                    })
+                } else {
+                    // Get the operands
+                    let operands = self.stack.split_off(stack_size - arity);
+
+                    // Try to run the operator
+                    let computed = if self.degrees {
+                        op.operate_degrees(&operands, self.ieee)
+                    } else if self.ieee {
+                        op.operate_ieee(&operands)
+                    } else {
+                        op.operate(&operands)
+                    };
+
+                    match computed {
+                        // If it fails, recover the tokens in the stack
+                        Err(e) => {
+                            self.stack.extend(operands);
+                            Err(e)
+                        },
+                        // Otherwise, push the result
+                        Ok(t) => {
+                            self.stack.push(t);
+                            Ok(())
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Displays the values one level per line, the top of the stack (level 1) coming last
+    ///
+    /// At most `limit` levels are shown, and lines are truncated to `width` characters.
+    pub fn levels(&self, limit: Option<usize>, width: Option<usize>) -> Levels<'_> {
+        Levels {
+            stack: self,
+            limit,
+            width
+        }
+    }
+
+    /// Value on top of the stack, if any
     pub fn result(&self) -> Option<&Token> {
-        self.stack.first()
+        // The result is the value on top of the stack
+        self.stack.last()
+    }
+}
+
+impl Default for Stack {
+    fn default() -> Stack {
+        Stack::new()
+    }
+}
+
+// Borrowed and owned views of a stack, as written with serde
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct StackRef<'a> {
+    values: &'a [Token],
+    last_op: Option<Operator>,
+    ieee: bool,
+    degrees: bool,
+    capacity: Option<usize>
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct StackData {
+    values: Vec<Token>,
+    last_op: Option<Operator>,
+    ieee: bool,
+    // Stacks written before the angle mode were in radians
+    #[serde(default)]
+    degrees: bool,
+    // Stacks written before the capacity could be changed had the default one
+    #[serde(default = "default_capacity")]
+    capacity: Option<usize>
+}
+
+#[cfg(feature = "serde")]
+fn default_capacity() -> Option<usize> {
+    Some(DEFAULT_CAPACITY)
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Stack {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StackRef {
+            values: &self.stack,
+            last_op: self.last_op,
+            ieee: self.ieee,
+            degrees: self.degrees,
+            capacity: self.capacity
+        }.serialize(serializer)
     }
 }
 
-impl<'a> fmt::Display for Stack<'a> {
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Stack {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Stack, D::Error> {
+        let data = StackData::deserialize(deserializer)?;
+        let mut stack = Stack::with_limit(data.capacity);
+
+        // Only values can be held by a stack, and no more than it can hold
+        stack.check_capacity(data.values.len()).map_err(de::Error::custom)?;
+
+        if data.values.iter().any(|t| !t.is_value()) {
+            return Err(de::Error::custom(PascalineError::TypeError));
+        }
+
+        stack.restore(data.values, data.last_op);
+        stack.set_ieee(data.ieee);
+        stack.set_degrees(data.degrees);
+
+        Ok(stack)
+    }
+}
+
+/// Display of a stack, one level per line
+///
+/// Levels hidden because of the limit are the deepest ones, summed up on the first line.
+#[derive(Debug, Clone, Copy)]
+pub struct Levels<'a> {
+    stack: &'a Stack,
+    limit: Option<usize>,
+    width: Option<usize>
+}
+
+impl fmt::Display for Levels<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let size = self.stack.size();
+        let shown = self.limit.map_or(size, |l| l.min(size));
+        let digits = shown.max(1).to_string().len();
+        let mut lines = Vec::with_capacity(shown + 1);
+
+        if shown < size {
+            lines.push(format!("\u{2026} {} more", size - shown));
+        }
+
+        for level in (1..=shown).rev() {
+            let label = format!("{:>digits$}: ", level, digits = digits);
+            let value = self.stack.stack[size - level].to_string();
+            let room = self.width.map_or(usize::MAX, |w| w.saturating_sub(label.len()));
+
+            // Long values are cut, with an ellipsis to show they are
+            if value.chars().count() > room {
+                let cut = value.chars().take(room.saturating_sub(1)).collect::<String>();
+
+                lines.push(format!("{}{}\u{2026}", label, cut));
+            } else {
+                lines.push(format!("{}{}", label, value));
+            }
+        }
+
+        write!(formatter, "{}", lines.join("\n"))
+    }
+}
+
+impl fmt::Display for Stack {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         write!(
             formatter,
@@ -142,3 +412,73 @@ impl<'a> fmt::Display for Stack<'a> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{ Operator, Stack, Token, DEFAULT_CAPACITY };
+    use crate::errors::PascalineError;
+
+    #[test]
+    fn test_top_of_stack() {
+        let mut stack = Stack::new();
+
+        assert_eq!(stack.result(), None);
+
+        stack.push(Token::new_integer(1)).unwrap();
+        stack.push(Token::new_integer(2)).unwrap();
+
+        // The result is the last value pushed, and DUP copies it rather than the first one
+        assert_eq!(stack.result(), Some(&Token::new_integer(2)));
+
+        stack.push(Token::Operator(Operator::Dup)).unwrap();
+
+        assert_eq!(stack.values(), &[Token::new_integer(1), Token::new_integer(2), Token::new_integer(2)]);
+        assert_eq!(stack.result(), Some(&Token::new_integer(2)));
+
+        match Stack::new().push(Token::Operator(Operator::Dup)) {
+            Err(PascalineError::EmptyStackError) => (),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut stack = Stack::with_capacity(2);
+
+        stack.push(Token::new_integer(1)).unwrap();
+        stack.push(Token::new_integer(2)).unwrap();
+
+        match stack.push(Token::new_integer(3)) {
+            Err(PascalineError::FullStackError { capacity: 2 }) => (),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        // Operators still apply, as they never make the stack grow
+        stack.push(Token::Operator(Operator::Add)).unwrap();
+        stack.set_capacity(None);
+
+        for i in 0..DEFAULT_CAPACITY {
+            stack.push(Token::new_integer(i as i32)).unwrap();
+        }
+
+        assert_eq!(stack.size(), DEFAULT_CAPACITY + 1);
+        assert_eq!(Stack::new().capacity(), Some(DEFAULT_CAPACITY));
+        assert_eq!(Stack::unbounded().capacity(), None);
+    }
+
+    #[test]
+    fn test_levels() {
+        let mut stack = Stack::new();
+
+        for i in 1..=12 {
+            stack.push(Token::new_integer(i)).unwrap();
+        }
+
+        stack.push(Token::new_float(0.5)).unwrap();
+
+        assert_eq!(format!("{}", stack.levels(Some(3), None)), "\u{2026} 10 more\n3: 11\n2: 12\n1: 0.500000");
+        assert_eq!(format!("{}", stack.levels(Some(1), Some(6))), "\u{2026} 12 more\n1: 0.\u{2026}");
+        assert_eq!(format!("{}", stack.levels(None, None)).lines().next(), Some("13: 1"));
+        assert_eq!(format!("{}", Stack::new().levels(Some(3), None)), "");
+    }
+}
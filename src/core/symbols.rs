@@ -12,9 +12,36 @@ pub const TAN: &str = "TAN";
 pub const ARCSIN: &str = "ARCSIN";
 pub const ARCCOS: &str = "ARCCOS";
 pub const ARCTAN: &str = "ARCTAN";
+pub const SINH: &str = "SINH";
+pub const COSH: &str = "COSH";
+pub const TANH: &str = "TANH";
+pub const ASINH: &str = "ASINH";
+pub const ACOSH: &str = "ACOSH";
+pub const ATANH: &str = "ATANH";
+pub const ATAN2: &str = "ATAN2";
+pub const HYPOT: &str = "HYPOT";
 pub const SQRT: &str = "SQRT";
 pub const EXP: &str = "EXP";
 pub const LN: &str = "LN";
+pub const LOG: &str = "LOG";
+pub const LOG2: &str = "LOG2";
+pub const LOGB: &str = "LOGB";
+pub const ALOG: &str = "ALOG";
+pub const EXPM1: &str = "EXPM1";
+pub const LN1P: &str = "LN1P";
+pub const CBRT: &str = "CBRT";
+pub const XROOT: &str = "XROOT";
+pub const ABS: &str = "ABS";
+pub const SIGN: &str = "SIGN";
+pub const FLOOR: &str = "FLOOR";
+pub const CEIL: &str = "CEIL";
+pub const ROUND: &str = "ROUND";
+pub const TRUNC: &str = "TRUNC";
+pub const FRAC: &str = "FRAC";
+pub const IP: &str = "IP";
+pub const MIN: &str = "MIN";
+pub const MAX: &str = "MAX";
+pub const RND: &str = "RND";
 pub const NUM: &str = "NUM";
 pub const DEN: &str = "DEN";
 pub const COMPLEX: &str = "$";
@@ -31,6 +58,9 @@ pub const GT: &str = ">";
 pub const AND: &str = "AND";
 pub const OR: &str = "OR";
 pub const NOT: &str = "NOT";
+pub const CONVERT: &str = "CONVERT";
+pub const UBASE: &str = "UBASE";
+pub const UVAL: &str = "UVAL";
 pub const DUP: &str = "DUP";
 pub const DROP: &str = "DROP";
 pub const SWAP: &str = "SWAP";
@@ -39,13 +69,72 @@ pub const LASTARGS: &str = "LASTARGS";
 pub const UNDO: &str = "UNDO";
 pub const REDO: &str = "REDO";
 pub const CLEAR: &str = "CLEAR";
+pub const SAVE: &str = "SAVE";
+pub const LOAD: &str = "LOAD";
+pub const HELP: &str = "HELP";
+pub const OPS: &str = "OPS";
+pub const CONST: &str = "CONST";
+pub const CONSTS: &str = "CONSTS";
+pub const DEG: &str = "DEG";
+pub const RAD: &str = "RAD";
+pub const PI: &str = "PI";
+pub const E: &str = "E";
+pub const PHI: &str = "PHI";
+pub const GAMMA: &str = "GAMMA";
+pub const INF: &str = "INF";
+pub const NAN: &str = "NAN";
 
 pub const LEFT_PARENTHESIS: &str = "(";
 pub const RIGHT_PARENTHESIS: &str = ")";
 pub const TRUE: &str = "TRUE";
 pub const FALSE: &str = "FALSE";
+pub const COMMENT: &str = "#";
+pub const QUOTE: &str = "\"";
 
-pub static OPERATORS: &'static [&str] = &[
+// Symbols which cannot be used by operators
+pub static RESERVED: &[&str] = &[
+    LEFT_PARENTHESIS,
+    RIGHT_PARENTHESIS,
+    TRUE,
+    FALSE,
+    COMMENT,
+    QUOTE,
+    SAVE,
+    LOAD,
+    HELP,
+    OPS,
+    CONST,
+    CONSTS,
+    DEG,
+    RAD,
+    PI,
+    E,
+    PHI,
+    GAMMA,
+    INF,
+    NAN
+];
+// Commands act on the calculator itself rather than on the stack
+pub static COMMANDS: &[&str] = &[
+    SAVE,
+    LOAD,
+    HELP,
+    OPS,
+    CONST,
+    CONSTS,
+    DEG,
+    RAD
+];
+// Constants push a value, rather than applying an operator
+pub static CONSTANTS: &[&str] = &[
+    PI,
+    E,
+    PHI,
+    GAMMA,
+    INF,
+    NAN
+];
+pub static OPERATORS: &[&str] = &[
     ADD,
     SUB,
     MUL,
@@ -60,9 +149,36 @@ pub static OPERATORS: &'static [&str] = &[
     ARCSIN,
     ARCCOS,
     ARCTAN,
+    SINH,
+    COSH,
+    TANH,
+    ASINH,
+    ACOSH,
+    ATANH,
+    ATAN2,
+    HYPOT,
     SQRT,
     EXP,
     LN,
+    LOG,
+    LOG2,
+    LOGB,
+    ALOG,
+    EXPM1,
+    LN1P,
+    CBRT,
+    XROOT,
+    ABS,
+    SIGN,
+    FLOOR,
+    CEIL,
+    ROUND,
+    TRUNC,
+    FRAC,
+    IP,
+    MIN,
+    MAX,
+    RND,
     NUM,
     DEN,
     COMPLEX,
@@ -79,6 +195,9 @@ pub static OPERATORS: &'static [&str] = &[
     AND,
     OR,
     NOT,
+    CONVERT,
+    UBASE,
+    UVAL,
     DUP,
     DROP,
     SWAP,
@@ -88,11 +207,12 @@ pub static OPERATORS: &'static [&str] = &[
     REDO,
     CLEAR
 ];
-pub static SYMBOLS: &'static [&str] = &[
+pub static SYMBOLS: &[&str] = &[
     LEFT_PARENTHESIS,
     RIGHT_PARENTHESIS,
     TRUE,
     FALSE,
+    COMMENT,
     ADD,
     SUB,
     MUL,
@@ -107,9 +227,36 @@ pub static SYMBOLS: &'static [&str] = &[
     ARCSIN,
     ARCCOS,
     ARCTAN,
+    SINH,
+    COSH,
+    TANH,
+    ASINH,
+    ACOSH,
+    ATANH,
+    ATAN2,
+    HYPOT,
     SQRT,
     EXP,
     LN,
+    LOG,
+    LOG2,
+    LOGB,
+    ALOG,
+    EXPM1,
+    LN1P,
+    CBRT,
+    XROOT,
+    ABS,
+    SIGN,
+    FLOOR,
+    CEIL,
+    ROUND,
+    TRUNC,
+    FRAC,
+    IP,
+    MIN,
+    MAX,
+    RND,
     NUM,
     DEN,
     COMPLEX,
@@ -126,6 +273,9 @@ pub static SYMBOLS: &'static [&str] = &[
     AND,
     OR,
     NOT,
+    CONVERT,
+    UBASE,
+    UVAL,
     DUP,
     DROP,
     SWAP,
@@ -133,5 +283,19 @@ pub static SYMBOLS: &'static [&str] = &[
     LASTARGS,
     UNDO,
     REDO,
-    CLEAR
+    CLEAR,
+    SAVE,
+    LOAD,
+    HELP,
+    OPS,
+    CONST,
+    CONSTS,
+    DEG,
+    RAD,
+    PI,
+    E,
+    PHI,
+    GAMMA,
+    INF,
+    NAN
 ];
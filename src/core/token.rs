@@ -1,121 +1,213 @@
 use super::types::{ Int, Float };
 use super::operator::Operator;
+use super::unit::Unit;
 use super::symbols::{ TRUE, FALSE };
 use crate::errors::PascalineError;
+#[cfg(feature = "serde")]
+use serde::{ Deserialize, Serialize };
 use std::fmt;
 
+// Magnitudes of the floats shown without an exponent
+const FIXED_MIN: Float = 1e-3;
+const FIXED_MAX: Float = 1e9;
+
+/// Item of a parsed text, either a value or an operator
+///
+/// With the `serde` feature, tokens are tagged with their type, so integers and floats are kept apart.
+/// NaN and infinite floats are written as the strings `"NaN"`, `"inf"` and `"-inf"`.
 #[derive(Debug, PartialEq, Copy, Clone)]
-pub enum Token<'a> {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "lowercase"))]
+pub enum Token {
     Integer(Int),
-    Float(Float),
+    Float(#[cfg_attr(feature = "serde", serde(with = "float"))] Float),
     Bool(bool),
-    Operator(&'a Operator),
+    // Value along with its unit, such as 9.81_m/s^2
+    Quantity(#[cfg_attr(feature = "serde", serde(with = "float"))] Float, Unit),
+    Operator(Operator),
     Ignored
 }
 
-impl<'a> Token<'a> {
-    pub fn new_integer(i: Int) -> Token<'a> {
+impl Token {
+    /// Creates an integer token
+    pub fn new_integer(i: Int) -> Token {
         Token::Integer(i)
     }
 
-    pub fn new_float(f: Float) -> Token<'a> {
-        if f.fract() == 0.0 {
+    /// Creates a float token, or an integer one if the value has no fractional part
+    pub fn new_float(f: Float) -> Token {
+        // Only use an integer when the value can be represented exactly
+        // NaN and infinite values have no fractional part and stay floats
+        if (f.fract() == 0.0) && (f >= Int::MIN as Float) && (f < -(Int::MIN as Float)) {
             Token::Integer(f as Int)
         } else {
             Token::Float(f)
         }
     }
 
-    pub fn new_bool(b: bool) -> Token<'a> {
+    /// Creates a token for a value with a unit, or a float one if there is no unit
+    pub fn new_quantity(f: Float, unit: Unit) -> Token {
+        if unit.is_none() {
+            Token::new_float(f)
+        } else {
+            Token::Quantity(f, unit)
+        }
+    }
+
+    /// Creates a boolean token
+    pub fn new_bool(b: bool) -> Token {
         Token::Bool(b)
     }
 
+    /// Creates a token for the built-in operator matching a symbol
     pub fn new_operator(o: &str) -> Result<Token, PascalineError> {
-        Operator::from_symbol(o).map(|op| Token::Operator(op))
+        Operator::from_symbol(o).map(Token::Operator)
     }
 
-    pub fn new_ignored() -> Token<'a> {
+    /// Creates a token standing for something which cannot be parsed
+    pub fn new_ignored() -> Token {
         Token::Ignored
     }
 
+    /// Whether the token stands for something which cannot be parsed
     pub fn is_ignored(&self) -> bool {
-        match self {
-            Token::Ignored => true,
-            _ => false
-        }
+        matches!(self, Token::Ignored)
     }
 
+    /// Whether the token can be pushed to a stack
     pub fn is_legit(&self) -> bool {
         !self.is_ignored()
     }
 
+    /// Whether the token is an integer or a float
     pub fn is_number(&self) -> bool {
-        match self {
-            Token::Integer(_) | Token::Float(_) => true,
-            _ => false
-        }
+        matches!(self, Token::Integer(_) | Token::Float(_))
     }
 
+    /// Whether the token is a boolean
     pub fn is_bool(&self) -> bool {
-        match self {
-            Token::Bool(_) => true,
-            _ => false
+        matches!(self, Token::Bool(_))
+    }
+
+    /// Whether the token is a value with a unit
+    pub fn is_quantity(&self) -> bool {
+        matches!(self, Token::Quantity(_, _))
+    }
+
+    /// Whether the token can be held by a stack, being a number, a boolean or a quantity
+    pub fn is_value(&self) -> bool {
+        self.is_number() || self.is_bool() || self.is_quantity()
+    }
+
+    /// Unit of the token, none for plain numbers and booleans
+    pub fn unit(&self) -> Unit {
+        match *self {
+            Token::Quantity(_, u) => u,
+            _ => Unit::none()
         }
     }
 
+    /// Whether the token is an operator
     pub fn is_operator(&self) -> bool {
-        match self {
-            Token::Operator(_) => true,
-            _ => false
-        }
+        matches!(self, Token::Operator(_))
     }
 
+    /// Whether the token is a null value (0, 0.0 or FALSE)
     pub fn is_zero(&self) -> bool {
-        match self {
-            &Token::Integer(i) => i == 0,
-            &Token::Float(f) => f == 0.0,
-            &Token::Bool(b) => !b,
+        match *self {
+            Token::Integer(i) => i == 0,
+            Token::Float(f) | Token::Quantity(f, _) => f == 0.0,
+            Token::Bool(b) => !b,
             _ => false
         }
     }
 
+    /// Value of the token as a float, if it is a value
+    ///
+    /// Quantities give their value in their own unit.
     pub fn as_float(&self) -> Option<Float> {
-        match self {
-            &Token::Integer(i) => Some(i as Float),
-            &Token::Float(f) => Some(f),
-            &Token::Bool(b) => Some(if b { 1.0 } else { 0.0 }),
+        match *self {
+            Token::Integer(i) => Some(i as Float),
+            Token::Float(f) | Token::Quantity(f, _) => Some(f),
+            Token::Bool(b) => Some(if b { 1.0 } else { 0.0 }),
             _ => None
         }
     }
 
+    /// Value of the token as a boolean, if it is a value
     pub fn as_bool(&self) -> Option<bool> {
-        match self {
-            &Token::Integer(i) => Some(i != 0),
-            &Token::Float(f) => Some(f != 0.0),
-            &Token::Bool(b) => Some(b),
+        match *self {
+            Token::Integer(i) => Some(i != 0),
+            Token::Float(f) => Some(f != 0.0),
+            Token::Bool(b) => Some(b),
             _ => None
         }
     }
 }
 
 
-impl<'a> fmt::Display for Token<'a> {
+impl fmt::Display for Token {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            &Token::Integer(i) => write!(formatter, "{}", i),
-            &Token::Float(f) => write!(formatter, "{:.6}", f),
-            &Token::Bool(b) => write!(formatter, "{}", if b { TRUE } else { FALSE }),
-            &Token::Operator(o) => write!(formatter, "{}", o),
-            &Token::Ignored => write!(formatter, ""),
+        match *self {
+            Token::Integer(i) => write!(formatter, "{}", i),
+            Token::Float(f) => write_float(formatter, f),
+            Token::Quantity(f, u) => write_float(formatter, f).and_then(|_| write!(formatter, "_{}", u)),
+            Token::Bool(b) => write!(formatter, "{}", if b { TRUE } else { FALSE }),
+            Token::Operator(o) => write!(formatter, "{}", o),
+            Token::Ignored => write!(formatter, ""),
         }
     }
 }
 
+// Floats are shown with 6 decimals, unless so small or large that the scientific notation is needed to read them
+fn write_float(formatter: &mut fmt::Formatter, f: Float) -> fmt::Result {
+    if f == 0.0 || !f.is_finite() || (FIXED_MIN..FIXED_MAX).contains(&f.abs()) {
+        write!(formatter, "{:.6}", f)
+    } else {
+        write!(formatter, "{:.6e}", f)
+    }
+}
+
+
+// JSON has no NaN nor infinite numbers, so they are written as strings to be read back
+#[cfg(feature = "serde")]
+mod float {
+    use crate::core::types::Float;
+    use serde::{ de, Deserialize, Deserializer, Serializer };
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Number(Float),
+        Text(String)
+    }
+
+    pub fn serialize<S: Serializer>(f: &Float, serializer: S) -> Result<S::Ok, S::Error> {
+        if f.is_finite() {
+            serializer.serialize_f32(*f)
+        } else {
+            serializer.serialize_str(&f.to_string())
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Float, D::Error> {
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(f) => Ok(f),
+            Repr::Text(t) => match t.as_str() {
+                "NaN" => Ok(Float::NAN),
+                "inf" => Ok(Float::INFINITY),
+                "-inf" => Ok(Float::NEG_INFINITY),
+                _ => Err(de::Error::custom(format!("invalid float '{}'", t)))
+            }
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use crate::core::Token;
     use crate::core::symbols::ADD;
+    use crate::core::types::Float;
 
     #[test]
     fn test_is_ignored() {
@@ -162,6 +254,16 @@ mod tests {
         assert!(!Token::new_ignored().is_operator());
     }
 
+    #[test]
+    fn test_new_float() {
+        assert_eq!(Token::new_float(2.0), Token::Integer(2));
+        assert_eq!(Token::new_float(-2.0), Token::Integer(-2));
+        assert_eq!(Token::new_float(2.5), Token::Float(2.5));
+        assert_eq!(Token::new_float(1e20), Token::Float(1e20));
+        assert_eq!(Token::new_float(Float::INFINITY), Token::Float(Float::INFINITY));
+        assert!(matches!(Token::new_float(Float::NAN), Token::Float(f) if f.is_nan()));
+    }
+
     #[test]
     fn test_as_float() {
         assert_eq!(Token::new_integer(0).as_float(), Some(0.0));
@@ -187,4 +289,69 @@ mod tests {
         assert_eq!(Token::new_operator(ADD).unwrap().as_bool(), None);
         assert_eq!(Token::new_ignored().as_bool(), None);
     }
+
+    #[test]
+    fn test_display() {
+        let meter = crate::core::Unit::parse("m").unwrap();
+
+        assert_eq!(Token::new_integer(-3).to_string(), "-3");
+        assert_eq!(Token::Float(3.5).to_string(), "3.500000");
+        assert_eq!(Token::Float(0.001).to_string(), "0.001000");
+        assert_eq!(Token::Float(-0.0).to_string(), "-0.000000");
+        assert_eq!(Token::Float(Float::NAN).to_string(), "NaN");
+        assert_eq!(Token::Float(Float::NEG_INFINITY).to_string(), "-inf");
+
+        // Tiny and huge values, such as physical constants, keep their digits
+        assert_eq!(Token::Float(6.626_07e-34).to_string(), "6.626070e-34");
+        assert_eq!(Token::Float(-0.000_5).to_string(), "-5.000000e-4");
+        assert_eq!(Token::Float(6.022_14e23).to_string(), "6.022140e23");
+        assert_eq!(Token::new_quantity(1.380_649e-23, meter).to_string(), "1.380649e-23_m");
+        assert_eq!(Token::new_bool(true).to_string(), "TRUE");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let tokens = vec![
+            Token::new_integer(3),
+            Token::Float(3.0),
+            Token::new_float(0.1),
+            Token::new_bool(true),
+            Token::new_operator(ADD).unwrap()
+        ];
+        let text = serde_json::to_string(&tokens).unwrap();
+
+        assert!(text.contains(r#"{"type":"operator","value":"+"}"#));
+        assert_eq!(serde_json::from_str::<Vec<Token>>(&text).unwrap(), tokens);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_special_floats() {
+        let meter = crate::core::Unit::parse("m").unwrap();
+        let tokens = vec![
+            Token::Float(Float::INFINITY),
+            Token::Float(Float::NEG_INFINITY),
+            Token::Float(-0.0),
+            Token::new_quantity(Float::INFINITY, meter)
+        ];
+        let text = serde_json::to_string(&tokens).unwrap();
+
+        assert!(text.contains(r#"{"type":"float","value":"inf"}"#));
+        assert!(text.contains(r#"{"type":"float","value":"-inf"}"#));
+        assert_eq!(serde_json::from_str::<Vec<Token>>(&text).unwrap(), tokens);
+
+        // Zero keeps its sign, and NaN cannot be compared so is checked on its own
+        match serde_json::from_str::<Vec<Token>>(&text).unwrap()[2] {
+            Token::Float(f) => assert!(f.is_sign_negative()),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        match serde_json::from_str::<Token>(&serde_json::to_string(&Token::Float(Float::NAN)).unwrap()) {
+            Ok(Token::Float(f)) => assert!(f.is_nan()),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+
+        assert!(serde_json::from_str::<Token>(r#"{"type":"float","value":"abc"}"#).is_err());
+    }
 }
@@ -1,3 +1,5 @@
 // Define some type alias to easily change what we use internally if needed
+/// Integer type used for values
 pub type Int = i32;
+/// Float type used for values
 pub type Float = f32;
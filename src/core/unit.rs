@@ -0,0 +1,371 @@
+#[cfg(feature = "serde")]
+use serde::{ de, Deserialize, Deserializer, Serialize, Serializer };
+use std::convert::TryFrom;
+use std::fmt;
+
+// Exponents of the SI base dimensions : length, mass, time, current, temperature, amount and luminous intensity
+type Dimension = [i32; 7];
+
+const LENGTH: Dimension = [1, 0, 0, 0, 0, 0, 0];
+const MASS: Dimension = [0, 1, 0, 0, 0, 0, 0];
+const TIME: Dimension = [0, 0, 1, 0, 0, 0, 0];
+const CURRENT: Dimension = [0, 0, 0, 1, 0, 0, 0];
+const TEMPERATURE: Dimension = [0, 0, 0, 0, 1, 0, 0];
+const AMOUNT: Dimension = [0, 0, 0, 0, 0, 1, 0];
+const INTENSITY: Dimension = [0, 0, 0, 0, 0, 0, 1];
+const FREQUENCY: Dimension = [0, 0, -1, 0, 0, 0, 0];
+const VOLUME: Dimension = [3, 0, 0, 0, 0, 0, 0];
+const SPEED: Dimension = [1, 0, -1, 0, 0, 0, 0];
+const FORCE: Dimension = [1, 1, -2, 0, 0, 0, 0];
+const PRESSURE: Dimension = [-1, 1, -2, 0, 0, 0, 0];
+const ENERGY: Dimension = [2, 1, -2, 0, 0, 0, 0];
+const POWER: Dimension = [2, 1, -3, 0, 0, 0, 0];
+const CHARGE: Dimension = [0, 0, 1, 1, 0, 0, 0];
+const VOLTAGE: Dimension = [2, 1, -3, -1, 0, 0, 0];
+const RESISTANCE: Dimension = [2, 1, -3, -2, 0, 0, 0];
+
+/// Unit known by the calculator, along with its value in SI base units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitDef {
+    pub symbol: &'static str,
+    pub description: &'static str,
+    pub factor: f64,
+    dimension: Dimension
+}
+
+const fn unit(symbol: &'static str, description: &'static str, factor: f64, dimension: Dimension) -> UnitDef {
+    UnitDef { symbol, description, factor, dimension }
+}
+
+// Distinct units combined in a single expression, which keeps units (and tokens) small
+const MAX_FACTORS: usize = 8;
+
+/// Built-in units, the SI base units coming first in the order of the dimensions
+///
+/// Units are referred to by their index, so there are fewer than 256 of them.
+pub static UNITS: &[UnitDef] = &[
+    unit("m", "metre", 1.0, LENGTH),
+    unit("kg", "kilogram", 1.0, MASS),
+    unit("s", "second", 1.0, TIME),
+    unit("A", "ampere", 1.0, CURRENT),
+    unit("K", "kelvin", 1.0, TEMPERATURE),
+    unit("mol", "mole", 1.0, AMOUNT),
+    unit("cd", "candela", 1.0, INTENSITY),
+    // Length
+    unit("km", "kilometre", 1e3, LENGTH),
+    unit("cm", "centimetre", 1e-2, LENGTH),
+    unit("mm", "millimetre", 1e-3, LENGTH),
+    unit("um", "micrometre", 1e-6, LENGTH),
+    unit("nm", "nanometre", 1e-9, LENGTH),
+    unit("in", "inch", 0.0254, LENGTH),
+    unit("ft", "foot", 0.3048, LENGTH),
+    unit("yd", "yard", 0.9144, LENGTH),
+    unit("mi", "mile", 1609.344, LENGTH),
+    unit("nmi", "nautical mile", 1852.0, LENGTH),
+    // Mass
+    unit("g", "gram", 1e-3, MASS),
+    unit("mg", "milligram", 1e-6, MASS),
+    unit("t", "tonne", 1e3, MASS),
+    unit("lb", "pound", 0.453_592_37, MASS),
+    unit("oz", "ounce", 0.028_349_523_125, MASS),
+    // Time
+    unit("ms", "millisecond", 1e-3, TIME),
+    unit("min", "minute", 60.0, TIME),
+    unit("h", "hour", 3600.0, TIME),
+    unit("d", "day", 86400.0, TIME),
+    // Current
+    unit("mA", "milliampere", 1e-3, CURRENT),
+    // Frequency
+    unit("Hz", "hertz", 1.0, FREQUENCY),
+    unit("kHz", "kilohertz", 1e3, FREQUENCY),
+    unit("MHz", "megahertz", 1e6, FREQUENCY),
+    // Volume
+    unit("L", "litre", 1e-3, VOLUME),
+    unit("mL", "millilitre", 1e-6, VOLUME),
+    unit("gal", "US gallon", 3.785_411_784e-3, VOLUME),
+    // Speed
+    unit("kph", "kilometre per hour", 1e3 / 3600.0, SPEED),
+    unit("mph", "mile per hour", 0.447_04, SPEED),
+    unit("kn", "knot", 1852.0 / 3600.0, SPEED),
+    // Force
+    unit("N", "newton", 1.0, FORCE),
+    unit("kN", "kilonewton", 1e3, FORCE),
+    unit("lbf", "pound-force", 4.448_221_615_260_5, FORCE),
+    // Pressure
+    unit("Pa", "pascal", 1.0, PRESSURE),
+    unit("kPa", "kilopascal", 1e3, PRESSURE),
+    unit("MPa", "megapascal", 1e6, PRESSURE),
+    unit("bar", "bar", 1e5, PRESSURE),
+    unit("atm", "standard atmosphere", 101_325.0, PRESSURE),
+    unit("psi", "pound per square inch", 6_894.757_293_168_361, PRESSURE),
+    // Energy
+    unit("J", "joule", 1.0, ENERGY),
+    unit("kJ", "kilojoule", 1e3, ENERGY),
+    unit("cal", "calorie", 4.184, ENERGY),
+    unit("kWh", "kilowatt hour", 3.6e6, ENERGY),
+    unit("eV", "electronvolt", 1.602_176_634e-19, ENERGY),
+    // Power
+    unit("W", "watt", 1.0, POWER),
+    unit("kW", "kilowatt", 1e3, POWER),
+    // Electricity
+    unit("C", "coulomb", 1.0, CHARGE),
+    unit("V", "volt", 1.0, VOLTAGE),
+    unit("ohm", "ohm", 1.0, RESISTANCE)
+];
+
+/// Unit expression attached to a value, such as `m/s^2`
+///
+/// Units are stored as the index and exponent of up to 8 built-in units, so they can be copied around like values.
+/// Exponents range from -127 to 127.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unit {
+    // Sorted by index, with the unused factors (of exponent 0) last, so equal units are stored the same way
+    factors: [(u8, i8); MAX_FACTORS]
+}
+
+impl Unit {
+    /// Unit without any factor, for plain numbers
+    pub fn none() -> Unit {
+        Unit {
+            factors: [(0, 0); MAX_FACTORS]
+        }
+    }
+
+    fn factors(&self) -> impl Iterator<Item = (&'static UnitDef, i8)> + '_ {
+        self.factors.iter().take_while(|(_, e)| *e != 0).map(|(i, e)| (&UNITS[usize::from(*i)], *e))
+    }
+
+    // Multiplies by a built-in unit, failing if the exponent gets out of range or there are too many factors
+    fn add(&mut self, index: usize, exponent: i8) -> Option<()> {
+        let index = u8::try_from(index).ok()?;
+        let count = self.factors().count();
+        let position = self.factors[..count].iter().position(|(i, _)| *i >= index).unwrap_or(count);
+
+        if (position < count) && (self.factors[position].0 == index) {
+            let sum = self.factors[position].1.checked_add(exponent).filter(|e| *e != i8::MIN)?;
+
+            if sum == 0 {
+                self.factors.copy_within(position + 1..count, position);
+                self.factors[count - 1] = (0, 0);
+            } else {
+                self.factors[position].1 = sum;
+            }
+        } else if exponent != 0 {
+            if (count == MAX_FACTORS) || (exponent == i8::MIN) {
+                return None;
+            }
+
+            self.factors.copy_within(position..count, position + 1);
+            self.factors[position] = (index, exponent);
+        }
+
+        Some(())
+    }
+
+    /// Parses a unit expression such as `kg*m/s^2`, with a single `/` and integer exponents
+    pub fn parse(text: &str) -> Option<Unit> {
+        let (numerator, denominator) = match text.split_once('/') {
+            Some((n, d)) => (n, d),
+            None => (text, "")
+        };
+        let mut unit = Unit::none();
+
+        if text.is_empty() || denominator.contains('/') {
+            return None;
+        }
+
+        for &(part, sign) in &[(numerator, 1), (denominator, -1)] {
+            // A lonely 1 stands for an empty numerator, as in 1/s
+            if part.is_empty() || (part == "1" && sign == 1) {
+                continue;
+            }
+
+            for factor in part.split('*') {
+                let (symbol, exponent) = match factor.split_once('^') {
+                    Some((s, e)) => (s, e.parse::<i8>().ok()?),
+                    None => (factor, 1)
+                };
+                let index = UNITS.iter().position(|u| u.symbol == symbol)?;
+
+                unit.add(index, exponent.checked_mul(sign)?)?;
+            }
+        }
+
+        Some(unit)
+    }
+
+    /// Whether the unit has no factor at all
+    pub fn is_none(&self) -> bool {
+        self.factors().next().is_none()
+    }
+
+    fn dimension(&self) -> Dimension {
+        let mut dimension = [0; 7];
+
+        for (def, exponent) in self.factors() {
+            for (d, base) in dimension.iter_mut().zip(def.dimension.iter()) {
+                *d += i32::from(exponent) * base;
+            }
+        }
+
+        dimension
+    }
+
+    /// Whether values in both units can be added or converted into each other
+    pub fn is_compatible(&self, other: &Unit) -> bool {
+        self.dimension() == other.dimension()
+    }
+
+    /// Value of 1 in this unit, expressed in SI base units
+    pub fn scale(&self) -> f64 {
+        self.factors().map(|(def, e)| def.factor.powi(i32::from(e))).product()
+    }
+
+    /// Same dimension, expressed with SI base units only
+    pub fn base(&self) -> Option<Unit> {
+        let mut unit = Unit::none();
+
+        // Base units are the first ones, in the order of the dimensions
+        for (index, exponent) in self.dimension().iter().enumerate() {
+            unit.add(index, i8::try_from(*exponent).ok()?)?;
+        }
+
+        Some(unit)
+    }
+
+    /// Product of two units, or `None` if an exponent gets out of range or there are too many factors
+    pub fn multiply(&self, other: &Unit) -> Option<Unit> {
+        let mut unit = *self;
+
+        for &(index, exponent) in other.factors.iter().take_while(|(_, e)| *e != 0) {
+            unit.add(usize::from(index), exponent)?;
+        }
+
+        Some(unit)
+    }
+
+    /// Quotient of two units, or `None` if an exponent gets out of range or there are too many factors
+    pub fn divide(&self, other: &Unit) -> Option<Unit> {
+        let mut unit = *self;
+
+        for &(index, exponent) in other.factors.iter().take_while(|(_, e)| *e != 0) {
+            unit.add(usize::from(index), exponent.checked_neg()?)?;
+        }
+
+        Some(unit)
+    }
+}
+
+impl Default for Unit {
+    fn default() -> Unit {
+        Unit::none()
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        // Positive exponents go before the slash, negative ones after it
+        let factors = |sign: i8| {
+            self.factors()
+                .filter(|(_, e)| e.signum() == sign)
+                .map(|(def, e)| match e.unsigned_abs() {
+                    1 => def.symbol.to_string(),
+                    a => format!("{}^{}", def.symbol, a)
+                })
+                .collect::<Vec<String>>()
+        };
+        let numerator = factors(1);
+        let denominator = factors(-1);
+
+        if numerator.is_empty() {
+            write!(formatter, "1")?;
+        } else {
+            write!(formatter, "{}", numerator.join("*"))?;
+        }
+
+        if !denominator.is_empty() {
+            write!(formatter, "/{}", denominator.join("*"))?;
+        }
+
+        Ok(())
+    }
+}
+
+// Units are written as their expression
+#[cfg(feature = "serde")]
+impl Serialize for Unit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Unit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Unit, D::Error> {
+        let text = String::deserialize(deserializer)?;
+
+        Unit::parse(&text).ok_or_else(|| de::Error::custom(format!("invalid unit '{}'", text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{ Token, Unit };
+
+    #[test]
+    fn test_parse() {
+        let unit = Unit::parse("m/s^2").unwrap();
+
+        assert_eq!(unit.to_string(), "m/s^2");
+        assert_eq!(Unit::parse("s^-2*m"), Some(unit));
+        assert_eq!(Unit::parse("1/s").unwrap().to_string(), "1/s");
+        assert!(Unit::parse("m/m").unwrap().is_none());
+        assert_eq!(Unit::parse("furlong"), None);
+        assert_eq!(Unit::parse("m/s/s"), None);
+        assert_eq!(Unit::parse("m^x"), None);
+        assert_eq!(Unit::parse(""), None);
+    }
+
+    #[test]
+    fn test_exponent_range() {
+        let unit = Unit::parse("m^-127").unwrap();
+        let meter = Unit::parse("m").unwrap();
+
+        // Exponents stay within -127 and 127, so they can always be negated
+        assert_eq!(unit.to_string(), "1/m^127");
+        assert_eq!(Unit::parse("m^-128"), None);
+        assert_eq!(Unit::parse("1/m^-128"), None);
+        assert_eq!(unit.divide(&meter), None);
+        assert_eq!(Unit::parse("m^127").unwrap().multiply(&meter), None);
+        assert_eq!(Unit::parse("m^127").unwrap().divide(&unit), None);
+        assert_eq!(Unit::parse("kN^127").unwrap().base(), None);
+    }
+
+    #[test]
+    fn test_factors() {
+        let all = Unit::parse("m*kg*s*A*K*mol*cd*km").unwrap();
+
+        // Factors are kept in order whatever they are written in, and dropped once cancelled
+        assert_eq!(Unit::parse("km*cd*mol*K*A*s*kg*m"), Some(all));
+        assert_eq!(all.to_string(), "m*kg*s*A*K*mol*cd*km");
+        assert_eq!(all.multiply(&Unit::parse("g").unwrap()), None);
+        assert_eq!(all.divide(&Unit::parse("kg").unwrap()).unwrap().multiply(&Unit::parse("g").unwrap()), Unit::parse("m*s*A*K*mol*cd*km*g"));
+        assert!(all.divide(&all).unwrap().is_none());
+
+        // Units stay small, so tokens are cheap to copy
+        assert_eq!(std::mem::size_of::<Unit>(), 16);
+        assert!(std::mem::size_of::<Token>() <= 32);
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let newton = Unit::parse("N").unwrap();
+        let base = Unit::parse("kg*m/s^2").unwrap();
+
+        assert!(newton.is_compatible(&base));
+        assert_eq!(newton.base(), Some(base));
+        assert!(!newton.is_compatible(&Unit::parse("J").unwrap()));
+        assert!((Unit::parse("psi").unwrap().scale() / Unit::parse("lbf/in^2").unwrap().scale() - 1.0).abs() < 1e-12);
+        assert_eq!(Unit::parse("km/h").unwrap().multiply(&Unit::parse("h").unwrap()), Unit::parse("km"));
+    }
+}
@@ -0,0 +1,93 @@
+use pascaline::{ Parser, Registry, WordKind };
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{ Context, Helper };
+use std::borrow::Cow;
+
+const RESET: &str = "\x1b[0m";
+
+// Colors of each kind of word, as ANSI escape sequences
+fn color(kind: WordKind) -> &'static str {
+    match kind {
+        WordKind::Number => "\x1b[36m",
+        WordKind::Bool => "\x1b[35m",
+        WordKind::Operator => "\x1b[32m",
+        WordKind::Command => "\x1b[33m",
+        WordKind::Argument => "\x1b[4m",
+        WordKind::Parenthesis => "\x1b[1m",
+        WordKind::Unbalanced => "\x1b[1;7;31m",
+        WordKind::Comment => "\x1b[90m",
+        WordKind::Unknown => "\x1b[31m"
+    }
+}
+
+/// Completes and highlights lines in the interactive mode, using the same parser as the evaluation
+pub struct EditorHelper {
+    parser: Parser,
+    registry: Registry,
+    colored: bool
+}
+
+impl EditorHelper {
+    pub fn new(registry: Registry, colored: bool) -> EditorHelper {
+        EditorHelper {
+            parser: Parser,
+            registry,
+            colored
+        }
+    }
+}
+
+impl Completer for EditorHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        // Only the word under the cursor is completed
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let prefix = &line[start..pos];
+
+        if prefix.is_empty() {
+            return Ok((start, Vec::new()));
+        }
+
+        Ok((start, self.parser.complete(prefix, &self.registry).into_iter().map(String::from).collect()))
+    }
+}
+
+impl Highlighter for EditorHelper {
+    fn highlight<'l>(&self, line: &'l str, _: usize) -> Cow<'l, str> {
+        if !self.colored {
+            return Cow::Borrowed(line);
+        }
+
+        let mut highlighted = String::with_capacity(line.len() * 2);
+        let mut end = 0;
+
+        for (span, kind) in self.parser.classify(line, &self.registry) {
+            highlighted.push_str(&line[end..span.start]);
+            highlighted.push_str(color(kind));
+            highlighted.push_str(&line[span.start..span.end]);
+            highlighted.push_str(RESET);
+            end = span.end;
+        }
+
+        highlighted.push_str(&line[end..]);
+
+        Cow::Owned(highlighted)
+    }
+
+    fn highlight_char(&self, _: &str, _: usize, _: bool) -> bool {
+        // Any change may alter the kind of words elsewhere, such as parentheses
+        self.colored
+    }
+}
+
+impl Hinter for EditorHelper {
+    type Hint = String;
+}
+
+impl Validator for EditorHelper {}
+
+impl Helper for EditorHelper {}
@@ -1,13 +1,20 @@
+use crate::core::Span;
 use thiserror::Error;
+use std::error::Error as StdError;
+use std::io;
+use std::path::PathBuf;
 
-// Errors for the app
-#[derive(Debug, Error)]
-pub enum PascalineError<'a> {
+/// Errors raised by the calculator
+///
+/// Each error owns its data so it can outlive the input it comes from.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum PascalineError {
     #[error("Failed to create operator from symbol : '{0}'")]
-    OperatorSymbolError(&'a str),
+    OperatorSymbolError(String),
     #[error("Not enough values to apply operator '{op}' (expected : {expected}, found : {found})")]
     ArityError {
-        op: &'a str,
+        op: String,
         expected: usize,
         found: usize
     },
@@ -15,16 +22,204 @@ pub enum PascalineError<'a> {
     TypeError,
     #[error("Division by zero is impossible")]
     ZeroDivisionError,
-    #[error("Computation stack is full")]
-    FullStackError,
+    #[error("Operand is out of the domain of operator '{op}'")]
+    DomainError {
+        op: String
+    },
+    #[error("Result of operator '{op}' is too large")]
+    OverflowError {
+        op: String
+    },
+    #[error("Computation stack is full ({capacity} values)")]
+    FullStackError {
+        capacity: usize
+    },
     #[error("Computation stack is empty")]
     EmptyStackError,
     #[error("Operation is not implemented yet")]
     NotImplementedError,
     #[error("No last operator to apply")]
     NoLastOperatorError,
-    // Just a work around for borrowing issues
-    // Using a flag to delay some computation in the code
-    #[error("")]
-    LastOperatorFlag
+    #[error("Symbol '{0}' is already in use")]
+    SymbolCollisionError(String),
+    #[error("Missing argument for '{0}'")]
+    ArgumentError(String),
+    #[error("Failed to access '{path}' : {reason}")]
+    IoError {
+        path: String,
+        reason: String
+    },
+    #[error("Invalid session : {0}")]
+    SessionFormatError(String),
+    #[error("Session format version {found} is not supported (expected : {supported} or older)")]
+    SessionVersionError {
+        found: u32,
+        supported: u32
+    },
+    #[error("Evaluation exceeded the limit of {limit} steps")]
+    StepLimitError {
+        limit: usize
+    },
+    #[error("Evaluation exceeded the time limit of {millis} ms")]
+    TimeLimitError {
+        millis: u128
+    },
+    #[error("Command '{0}' is not allowed here")]
+    SandboxError(String),
+    #[error("Text is not valid UTF-8")]
+    EncodingError,
+    #[error("Unknown constant '{0}'")]
+    ConstantError(String),
+    #[error("Units '{left}' and '{right}' have different dimensions")]
+    DimensionError {
+        left: String,
+        right: String
+    },
+    #[error("Pointer '{0}' is null")]
+    NullPointerError(String),
+    #[error("Internal error : {0}")]
+    InternalError(String),
+    #[error("Unknown word '{0}'")]
+    UnknownWordError(String),
+    #[error("Unit '{0}' is unknown or out of range")]
+    UnitError(String)
+}
+
+impl PascalineError {
+    /// Stable code of the error, which never changes across versions
+    // Codes are part of the public API : never change nor reuse one, only add new ones
+    pub fn code(&self) -> u16 {
+        match self {
+            PascalineError::OperatorSymbolError(_) => 1,
+            PascalineError::ArityError { .. } => 2,
+            PascalineError::TypeError => 3,
+            PascalineError::ZeroDivisionError => 4,
+            PascalineError::FullStackError { .. } => 5,
+            PascalineError::EmptyStackError => 6,
+            PascalineError::NotImplementedError => 7,
+            PascalineError::NoLastOperatorError => 8,
+            PascalineError::DomainError { .. } => 9,
+            PascalineError::OverflowError { .. } => 10,
+            PascalineError::SymbolCollisionError(_) => 11,
+            PascalineError::ArgumentError(_) => 12,
+            PascalineError::IoError { .. } => 13,
+            PascalineError::SessionFormatError(_) => 14,
+            PascalineError::SessionVersionError { .. } => 15,
+            PascalineError::StepLimitError { .. } => 16,
+            PascalineError::TimeLimitError { .. } => 17,
+            PascalineError::SandboxError(_) => 18,
+            PascalineError::EncodingError => 19,
+            PascalineError::ConstantError(_) => 20,
+            PascalineError::DimensionError { .. } => 21,
+            PascalineError::NullPointerError(_) => 22,
+            PascalineError::InternalError(_) => 23,
+            PascalineError::UnknownWordError(_) => 24,
+            PascalineError::UnitError(_) => 25
+        }
+    }
+}
+
+/// Error raised while evaluating a text, located on the token that caused it
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("Failed to evaluate '{token}'")]
+pub struct EvalError {
+    #[source]
+    pub error: PascalineError,
+    pub span: Span,
+    pub token: String
+}
+
+impl EvalError {
+    /// Stable code of the underlying error
+    pub fn code(&self) -> u16 {
+        self.error.code()
+    }
+}
+
+/// Error raised while running a script, located in the source file
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    #[error("Failed to read script '{}'", .path.display())]
+    ReadError {
+        path: PathBuf,
+        #[source]
+        source: io::Error
+    },
+    #[error("{}:{}:{}", .path.display(), .line, .column)]
+    RunError {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        #[source]
+        source: Box<EvalError>
+    }
+}
+
+/// Formats an error along with all its causes, on a single line
+pub fn report(error: &dyn StdError) -> String {
+    let mut message = format!("{}", error);
+    let mut source = error.source();
+
+    while let Some(e) = source {
+        message.push_str(&format!(": {}", e));
+        source = e.source();
+    }
+
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::Span;
+    use crate::errors::{ report, EvalError, PascalineError };
+
+    #[test]
+    fn test_codes_are_unique() {
+        let errors = vec![
+            PascalineError::OperatorSymbolError(String::from("?")),
+            PascalineError::ArityError { op: String::from("+"), expected: 2, found: 1 },
+            PascalineError::TypeError,
+            PascalineError::ZeroDivisionError,
+            PascalineError::FullStackError { capacity: 0 },
+            PascalineError::EmptyStackError,
+            PascalineError::NotImplementedError,
+            PascalineError::NoLastOperatorError,
+            PascalineError::DomainError { op: String::from("LN") },
+            PascalineError::OverflowError { op: String::from("EXP") },
+            PascalineError::SymbolCollisionError(String::from("+")),
+            PascalineError::ArgumentError(String::from("SAVE")),
+            PascalineError::IoError { path: String::from("a.json"), reason: String::from("?") },
+            PascalineError::SessionFormatError(String::from("?")),
+            PascalineError::SessionVersionError { found: 2, supported: 1 },
+            PascalineError::StepLimitError { limit: 100 },
+            PascalineError::TimeLimitError { millis: 100 },
+            PascalineError::SandboxError(String::from("LOAD")),
+            PascalineError::EncodingError,
+            PascalineError::ConstantError(String::from("_x")),
+            PascalineError::DimensionError { left: String::from("m"), right: String::from("s") },
+            PascalineError::NullPointerError(String::from("calculator")),
+            PascalineError::InternalError(String::from("?")),
+            PascalineError::UnknownWordError(String::from("?")),
+            PascalineError::UnitError(String::from("degC"))
+        ];
+        let mut codes = errors.iter().map(|e| e.code()).collect::<Vec<u16>>();
+
+        codes.sort_unstable();
+        codes.dedup();
+
+        assert_eq!(codes.len(), errors.len());
+    }
+
+    #[test]
+    fn test_eval_error_source() {
+        let error = EvalError {
+            error: PascalineError::ZeroDivisionError,
+            span: Span { start: 4, end: 5 },
+            token: String::from("/")
+        };
+
+        assert_eq!(error.code(), 4);
+        assert_eq!(format!("{}", error), "Failed to evaluate '/'");
+        assert_eq!(report(&error), "Failed to evaluate '/': Division by zero is impossible");
+    }
 }
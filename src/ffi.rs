@@ -0,0 +1,337 @@
+//! C API, to embed the calculator in other languages
+//!
+//! Every function accepts null pointers and reports them as errors instead of crashing,
+//! and panics are caught rather than unwinding into the caller.
+
+use crate::calculator::Calculator;
+use crate::core::Token;
+use crate::errors::{ report, PascalineError };
+use std::any::Any;
+use std::ffi::{ CStr, CString };
+use std::os::raw::c_char;
+use std::panic::{ self, AssertUnwindSafe };
+use std::ptr;
+
+/// Calculator handle, along with the last error and messages raised through it
+pub struct PascalineCalculator {
+    calculator: Calculator,
+    error: Option<(u16, CString)>,
+    output: Option<CString>
+}
+
+/// Type of a value held by the stack
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PascalineType {
+    Integer,
+    Float,
+    Bool,
+    Quantity
+}
+
+/// Content of a value, to be read according to its type
+///
+/// Quantities are read as their magnitude, in their own unit, which has no C counterpart.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union PascalineData {
+    pub integer: i32,
+    pub floating: f32,
+    pub boolean: bool
+}
+
+/// Value held by the stack, tagged with its type
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PascalineValue {
+    pub kind: PascalineType,
+    pub data: PascalineData
+}
+
+impl PascalineCalculator {
+    fn fail(&mut self, error: u16, message: &str) -> u16 {
+        self.error = Some((error, c_string(message)));
+        error
+    }
+
+    fn fail_with(&mut self, error: PascalineError) -> u16 {
+        self.fail(error.code(), &error.to_string())
+    }
+}
+
+fn c_string(text: &str) -> CString {
+    // Messages never contain nul bytes, but stay safe if one ever does
+    CString::new(text.replace('\0', " ")).unwrap_or_default()
+}
+
+// Runs the body of a C function, giving back `fallback` if it panics
+fn guard<T>(fallback: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(fallback)
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(m) => *m,
+        Err(payload) => payload.downcast_ref::<&str>().map_or_else(|| String::from("panic"), |m| m.to_string())
+    }
+}
+
+/// Creates a calculator, to be freed with `pascaline_free`
+#[no_mangle]
+pub extern "C" fn pascaline_new() -> *mut PascalineCalculator {
+    guard(ptr::null_mut(), || Box::into_raw(Box::new(PascalineCalculator {
+        calculator: Calculator::new(),
+        error: None,
+        output: None
+    })))
+}
+
+/// Frees a calculator created with `pascaline_new`
+///
+/// # Safety
+///
+/// `calculator` must come from `pascaline_new` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn pascaline_free(calculator: *mut PascalineCalculator) {
+    if !calculator.is_null() {
+        guard((), || drop(Box::from_raw(calculator)));
+    }
+}
+
+/// Evaluates a nul-terminated UTF-8 text, returning 0 on success or the code of the error
+///
+/// # Safety
+///
+/// `calculator` must come from `pascaline_new`, and `text` must be nul-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn pascaline_eval(calculator: *mut PascalineCalculator, text: *const c_char) -> u16 {
+    // Without a calculator, there is nowhere to keep the message
+    let handle = match calculator.as_mut() {
+        Some(h) => h,
+        None => return PascalineError::NullPointerError(String::from("calculator")).code()
+    };
+
+    handle.error = None;
+    handle.output = None;
+
+    if text.is_null() {
+        return handle.fail_with(PascalineError::NullPointerError(String::from("text")));
+    }
+
+    let text = match CStr::from_ptr(text).to_str() {
+        Ok(t) => t,
+        Err(_) => return handle.fail_with(PascalineError::EncodingError)
+    };
+
+    let calculator = &mut handle.calculator;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let result = calculator.eval(text);
+
+        // Messages are taken on every evaluation, so they never pile up
+        (result, calculator.take_output())
+    }));
+
+    match result {
+        Ok((result, output)) => {
+            if !output.is_empty() {
+                handle.output = Some(c_string(&output.join("\n")));
+            }
+
+            match result {
+                Ok(_) => 0,
+                Err(e) => handle.fail(e.code(), &report(&e))
+            }
+        },
+        Err(payload) => handle.fail_with(PascalineError::InternalError(panic_message(payload)))
+    }
+}
+
+/// Number of values in the stack
+///
+/// # Safety
+///
+/// `calculator` must come from `pascaline_new`.
+#[no_mangle]
+pub unsafe extern "C" fn pascaline_depth(calculator: *const PascalineCalculator) -> usize {
+    guard(0, || calculator.as_ref().map_or(0, |h| h.calculator.stack().size()))
+}
+
+/// Reads the value at `index` (0 being the deepest), returning false if there is none
+///
+/// # Safety
+///
+/// `calculator` must come from `pascaline_new`, and `value` must point to a writable value.
+#[no_mangle]
+pub unsafe extern "C" fn pascaline_get(
+    calculator: *const PascalineCalculator,
+    index: usize,
+    value: *mut PascalineValue
+) -> bool {
+    guard(false, || get(calculator, index, value))
+}
+
+unsafe fn get(calculator: *const PascalineCalculator, index: usize, value: *mut PascalineValue) -> bool {
+    let token = match calculator.as_ref().and_then(|h| h.calculator.stack().values().get(index)) {
+        Some(t) => t,
+        None => return false
+    };
+    let converted = match *token {
+        Token::Integer(i) => PascalineValue { kind: PascalineType::Integer, data: PascalineData { integer: i } },
+        Token::Float(f) => PascalineValue { kind: PascalineType::Float, data: PascalineData { floating: f } },
+        Token::Bool(b) => PascalineValue { kind: PascalineType::Bool, data: PascalineData { boolean: b } },
+        Token::Quantity(f, _) => PascalineValue { kind: PascalineType::Quantity, data: PascalineData { floating: f } },
+        // Operators are applied as soon as they are pushed, so they never end up in the stack
+        Token::Operator(_) | Token::Ignored => return false
+    };
+
+    match value.as_mut() {
+        Some(v) => {
+            *v = converted;
+            true
+        },
+        None => false
+    }
+}
+
+/// Code of the last error raised by `pascaline_eval`, or 0 if it succeeded
+///
+/// # Safety
+///
+/// `calculator` must come from `pascaline_new`.
+#[no_mangle]
+pub unsafe extern "C" fn pascaline_error_code(calculator: *const PascalineCalculator) -> u16 {
+    guard(0, || calculator.as_ref().and_then(|h| h.error.as_ref()).map_or(0, |e| e.0))
+}
+
+/// Message of the last error raised by `pascaline_eval`, or null if it succeeded
+///
+/// The message is owned by the calculator, and lives until the next call to `pascaline_eval`.
+///
+/// # Safety
+///
+/// `calculator` must come from `pascaline_new`.
+#[no_mangle]
+pub unsafe extern "C" fn pascaline_error_message(calculator: *const PascalineCalculator) -> *const c_char {
+    guard(ptr::null(), || calculator.as_ref().and_then(|h| h.error.as_ref()).map_or(ptr::null(), |e| e.1.as_ptr()))
+}
+
+/// Messages written by commands such as `HELP` during the last call to `pascaline_eval`, one per line,
+/// or null if there are none
+///
+/// The messages are owned by the calculator, and live until the next call to `pascaline_eval`.
+///
+/// # Safety
+///
+/// `calculator` must come from `pascaline_new`.
+#[no_mangle]
+pub unsafe extern "C" fn pascaline_output(calculator: *const PascalineCalculator) -> *const c_char {
+    guard(ptr::null(), || calculator.as_ref().and_then(|h| h.output.as_ref()).map_or(ptr::null(), |o| o.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ffi::*;
+    use std::ffi::{ CStr, CString };
+    use std::mem::MaybeUninit;
+    use std::ptr;
+
+    #[test]
+    fn test_eval() {
+        let text = CString::new("1.5 2 + TRUE").unwrap();
+
+        unsafe {
+            let c = pascaline_new();
+            let mut value = MaybeUninit::<PascalineValue>::uninit();
+
+            assert_eq!(pascaline_eval(c, text.as_ptr()), 0);
+            assert_eq!(pascaline_depth(c), 2);
+            assert!(pascaline_get(c, 0, value.as_mut_ptr()));
+            assert_eq!(value.assume_init().kind, PascalineType::Float);
+            assert_eq!(value.assume_init().data.floating, 3.5);
+            assert!(pascaline_get(c, 1, value.as_mut_ptr()));
+            assert!(value.assume_init().data.boolean);
+            assert!(!pascaline_get(c, 2, value.as_mut_ptr()));
+            assert!(pascaline_error_message(c).is_null());
+            assert!(pascaline_output(c).is_null());
+
+            pascaline_free(c);
+        }
+    }
+
+    #[test]
+    fn test_quantity() {
+        let text = CString::new("2.5_km").unwrap();
+
+        unsafe {
+            let c = pascaline_new();
+            let mut value = MaybeUninit::<PascalineValue>::uninit();
+
+            // Quantities are told apart from the values that are missing
+            assert_eq!(pascaline_eval(c, text.as_ptr()), 0);
+            assert!(pascaline_get(c, 0, value.as_mut_ptr()));
+            assert_eq!(value.assume_init().kind, PascalineType::Quantity);
+            assert_eq!(value.assume_init().data.floating, 2.5);
+            assert!(!pascaline_get(c, 1, value.as_mut_ptr()));
+            assert!(pascaline_output(c).is_null());
+
+            pascaline_free(c);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let text = CString::new("1 0 /").unwrap();
+        let invalid = [0xff_u8, 0];
+
+        unsafe {
+            let c = pascaline_new();
+
+            assert_eq!(pascaline_eval(c, text.as_ptr()), 4);
+            assert_eq!(pascaline_error_code(c), 4);
+            assert_eq!(
+                CStr::from_ptr(pascaline_error_message(c)).to_str().unwrap(),
+                "Failed to evaluate '/': Division by zero is impossible"
+            );
+            assert_eq!(pascaline_eval(c, invalid.as_ptr() as *const _), 19);
+            assert_eq!(pascaline_eval(c, ptr::null()), 22);
+            assert_eq!(
+                CStr::from_ptr(pascaline_error_message(c)).to_str().unwrap(),
+                "Pointer 'text' is null"
+            );
+            assert_eq!(pascaline_eval(ptr::null_mut(), text.as_ptr()), 22);
+            assert_eq!(pascaline_depth(ptr::null()), 0);
+
+            pascaline_free(c);
+        }
+    }
+
+    #[test]
+    fn test_output() {
+        let help = CString::new("HELP DUP HELP DROP").unwrap();
+        let text = CString::new("1").unwrap();
+
+        unsafe {
+            let c = pascaline_new();
+
+            assert_eq!(pascaline_eval(c, help.as_ptr()), 0);
+
+            let output = CStr::from_ptr(pascaline_output(c)).to_str().unwrap();
+
+            assert!(output.starts_with("DUP : "));
+            assert!(output.contains("\nDROP : "));
+
+            // Messages only last until the next evaluation
+            assert_eq!(pascaline_eval(c, text.as_ptr()), 0);
+            assert!(pascaline_output(c).is_null());
+
+            pascaline_free(c);
+        }
+    }
+
+    #[test]
+    fn test_panics() {
+        assert_eq!(guard(0, || panic!("Boom")), 0);
+        assert_eq!(panic::catch_unwind(|| panic!("Boom {}", 1)).map_err(panic_message), Err(String::from("Boom 1")));
+        assert_eq!(panic::catch_unwind(|| panic!("Boom")).map_err(panic_message), Err(String::from("Boom")));
+    }
+}
@@ -0,0 +1,542 @@
+use crate::calculator::{ Calculator, Limits };
+use crate::core::Registry;
+use crate::errors::report;
+use crate::rpc::{ stack_json, ConnectionSlot };
+use serde_json::{ json, Value as Json };
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::io::{ self, BufRead, BufReader, Read, Write };
+use std::net::{ TcpListener, TcpStream };
+use std::sync::atomic::{ AtomicU64, AtomicUsize, Ordering };
+use std::sync::{ Arc, Mutex };
+use std::thread;
+use std::time::{ Duration, Instant };
+
+// Headers are not expected to be anywhere near this size
+const MAX_HEADERS: usize = 8 * 1024;
+
+/// Settings of the HTTP API, mostly bounds so a single client cannot hang the server
+#[derive(Debug, Clone, Copy)]
+pub struct HttpConfig {
+    /// Maximum size of a request body, in bytes
+    pub max_body: usize,
+    /// Bounds applied to each evaluation
+    pub limits: Limits,
+    /// Sessions unused for this long are dropped
+    pub session_ttl: Duration,
+    /// Maximum number of live sessions
+    pub max_sessions: usize,
+    /// Maximum number of connections served at once
+    pub max_connections: usize,
+    /// Maximum time to wait for each read from a client, and for each write to it
+    pub read_timeout: Duration,
+    /// Maximum time for a client to send its whole request, however slowly it trickles in
+    pub request_timeout: Duration
+}
+
+impl Default for HttpConfig {
+    fn default() -> HttpConfig {
+        HttpConfig {
+            max_body: 64 * 1024,
+            limits: Limits {
+                max_steps: Some(10_000),
+                timeout: Some(Duration::from_millis(100))
+            },
+            session_ttl: Duration::from_secs(600),
+            max_sessions: 1024,
+            max_connections: 64,
+            read_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(10)
+        }
+    }
+}
+
+// Each calculator is locked on its own, so evaluations in different sessions run side by side
+type SharedCalculator = Arc<Mutex<Calculator>>;
+
+#[derive(Debug)]
+struct Entry {
+    calculator: SharedCalculator,
+    used: Instant
+}
+
+/// Minimal HTTP API evaluating expressions in sessions addressed by id
+#[derive(Debug)]
+pub struct HttpServer {
+    config: HttpConfig,
+    sessions: Mutex<HashMap<String, Entry>>,
+    // Ids are hashed with a random key, so they cannot be guessed from one another
+    hasher: RandomState,
+    counter: AtomicU64,
+    connections: Arc<AtomicUsize>
+}
+
+impl HttpServer {
+    /// Creates a server without any session
+    pub fn new(config: HttpConfig) -> HttpServer {
+        HttpServer {
+            config,
+            sessions: Mutex::new(HashMap::new()),
+            hasher: RandomState::new(),
+            counter: AtomicU64::new(0),
+            connections: Arc::new(AtomicUsize::new(0))
+        }
+    }
+
+    /// Serves connections until the listener is closed, each one on its own thread
+    pub fn serve(self, listener: TcpListener) -> io::Result<()> {
+        let server = Arc::new(self);
+
+        for stream in listener.incoming() {
+            // A client resetting its connection, or running out of file descriptors for a while, must not stop the server
+            let mut stream = match stream {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Failed to accept a connection : {}", e);
+                    continue;
+                }
+            };
+
+            // Busy clients are turned away at once, rather than piling up threads
+            let slot = match ConnectionSlot::take(&server.connections, server.config.max_connections) {
+                Some(s) => s,
+                None => {
+                    refuse(&mut stream);
+                    continue;
+                }
+            };
+            let server = Arc::clone(&server);
+
+            thread::spawn(move || {
+                let _slot = slot;
+
+                server.handle_connection(stream);
+            });
+        }
+
+        Ok(())
+    }
+
+    fn handle_connection(&self, mut stream: TcpStream) {
+        let response = match self.read_request(&stream) {
+            Ok((method, path, body)) => self.handle(&method, &path, &body),
+            Err(response) => response
+        };
+
+        self.respond(&mut stream, response);
+    }
+
+    fn respond(&self, stream: &mut TcpStream, response: (u16, String)) {
+        // Nothing can be done if the client is gone already, or does not read its response
+        let _ = stream.set_write_timeout(Some(self.config.read_timeout))
+            .and_then(|_| stream.write_all(format_response(response).as_bytes()))
+            .and_then(|_| stream.flush());
+    }
+
+    fn read_request(&self, stream: &TcpStream) -> Result<(String, String, Vec<u8>), (u16, String)> {
+        let bad_request = |e: io::Error| match e.kind() {
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => error(408, "Request was not received in time"),
+            _ => error(400, &e.to_string())
+        };
+        // A client which never finishes its request must not hold a thread forever
+        let deadline = Deadline {
+            stream,
+            timeout: self.config.read_timeout,
+            end: Instant::now() + self.config.request_timeout
+        };
+        let mut reader = BufReader::new(deadline).take(MAX_HEADERS as u64);
+        let mut line = String::new();
+
+        reader.read_line(&mut line).map_err(bad_request)?;
+
+        let mut parts = line.split_whitespace();
+        let (method, path) = match (parts.next(), parts.next()) {
+            (Some(m), Some(p)) => (m.to_string(), p.to_string()),
+            _ => return Err(error(400, "Malformed request line"))
+        };
+        let mut length = 0;
+
+        loop {
+            line.clear();
+
+            if reader.read_line(&mut line).map_err(bad_request)? == 0 {
+                return Err(error(431, "Headers are too large or incomplete"));
+            }
+
+            let header = line.trim_end();
+
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse::<usize>().map_err(|_| error(400, "Invalid Content-Length"))?;
+                }
+            }
+        }
+
+        // Refuse large bodies before reading them
+        if length > self.config.max_body {
+            return Err(error(413, &format!("Request body is larger than {} bytes", self.config.max_body)));
+        }
+
+        let mut body = vec![0; length];
+        let mut reader = reader.into_inner();
+
+        reader.read_exact(&mut body).map_err(bad_request)?;
+
+        Ok((method, path, body))
+    }
+
+    /// Handles a single request, returning the status and the JSON body of the response
+    pub fn handle(&self, method: &str, path: &str, body: &[u8]) -> (u16, String) {
+        match (method, path) {
+            ("POST", "/eval") => self.eval(body),
+            ("GET", "/operators") => (200, operators().to_string()),
+            (_, "/eval") | (_, "/operators") => error(405, &format!("Method {} is not allowed", method)),
+            _ => error(404, &format!("Unknown path '{}'", path))
+        }
+    }
+
+    fn eval(&self, body: &[u8]) -> (u16, String) {
+        let request = match serde_json::from_slice::<Json>(body) {
+            Ok(r) => r,
+            Err(e) => return error(400, &e.to_string())
+        };
+        let expression = match request.get("expression").and_then(Json::as_str) {
+            Some(e) => e,
+            None => return error(400, "Missing string field 'expression'")
+        };
+        let (id, calculator) = match self.session(request.get("session")) {
+            Ok(s) => s,
+            Err(response) => return response
+        };
+        // A panic while evaluating leaves the calculator usable, so keep going
+        let mut calculator = calculator.lock().unwrap_or_else(|e| e.into_inner());
+        let result = calculator.eval(expression);
+        let mut response = stack_json(calculator.stack());
+
+        response["session"] = json!(id);
+        response["output"] = json!(calculator.take_output());
+
+        match result {
+            Ok(_) => (200, response.to_string()),
+            Err(e) => {
+                response["error"] = json!({
+                    "code": e.code(),
+                    "message": report(&e),
+                    "token": e.token,
+                    "column": e.span.column(expression)
+                });
+                (422, response.to_string())
+            }
+        }
+    }
+
+    // Finds or creates the session, only holding the sessions while looking it up
+    fn session(&self, id: Option<&Json>) -> Result<(String, SharedCalculator), (u16, String)> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+
+        // Expired sessions are dropped lazily, on the next request
+        sessions.retain(|_, e| now.duration_since(e.used) < self.config.session_ttl);
+
+        let id = match id {
+            None | Some(Json::Null) => {
+                if sessions.len() >= self.config.max_sessions {
+                    return Err(error(503, "Too many sessions"));
+                }
+
+                let id = self.new_id();
+                let mut calculator = Calculator::new();
+
+                // Clients are untrusted : bound their evaluations and keep them off the file system
+                calculator.set_limits(self.config.limits);
+                calculator.set_sandboxed(true);
+                sessions.insert(id.clone(), Entry { calculator: Arc::new(Mutex::new(calculator)), used: now });
+                id
+            },
+            Some(Json::String(id)) if sessions.contains_key(id) => id.clone(),
+            Some(Json::String(id)) => return Err(error(404, &format!("Unknown or expired session '{}'", id))),
+            Some(_) => return Err(error(400, "Field 'session' must be a string"))
+        };
+        // Safe to unwrap, the session has just been found or created
+        let entry = sessions.get_mut(&id).unwrap();
+
+        entry.used = now;
+
+        Ok((id, Arc::clone(&entry.calculator)))
+    }
+
+    fn new_id(&self) -> String {
+        let count = self.counter.fetch_add(1, Ordering::Relaxed);
+
+        format!("{:016x}", self.hasher.hash_one(count))
+    }
+}
+
+// Reads from a client, failing once its whole request has taken too long
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    timeout: Duration,
+    end: Instant
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let left = self.end.saturating_duration_since(Instant::now());
+
+        if left.is_zero() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "Request was not received in time"));
+        }
+
+        self.stream.set_read_timeout(Some(left.min(self.timeout)))?;
+        self.stream.read(buffer)
+    }
+}
+
+fn operators() -> Json {
+    let registry = Registry::new();
+    let operators = registry.operators().iter().map(|o| {
+        let metadata = o.metadata();
+
+        json!({
+            "symbol": metadata.symbol,
+            "arity": metadata.arity,
+            "category": metadata.category.name(),
+            "description": metadata.description,
+            "effect": metadata.effect,
+            "examples": metadata.examples
+        })
+    }).collect::<Vec<Json>>();
+
+    json!({ "operators": operators })
+}
+
+fn format_response((status, body): (u16, String)) -> String {
+    format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    )
+}
+
+fn refuse(stream: &mut TcpStream) {
+    let response = format_response(error(503, "Too many connections"));
+
+    // The accept loop never waits on a client : the response is dropped if it cannot be written right away
+    let _ = stream.set_nonblocking(true).and_then(|_| stream.write_all(response.as_bytes()));
+}
+
+fn error(status: u16, message: &str) -> (u16, String) {
+    (status, json!({ "error": { "message": message } }).to_string())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http::{ HttpConfig, HttpServer };
+    use crate::rpc::ConnectionSlot;
+    use serde_json::{ json, Value as Json };
+    use std::io::{ Read, Write };
+    use std::net::{ TcpListener, TcpStream };
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use std::time::Duration;
+
+    fn post(server: &HttpServer, body: Json) -> (u16, Json) {
+        let (status, body) = server.handle("POST", "/eval", body.to_string().as_bytes());
+
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[test]
+    fn test_eval_session() {
+        let server = HttpServer::new(HttpConfig::default());
+        let (status, first) = post(&server, json!({ "expression": "2 3 +" }));
+
+        assert_eq!(status, 200);
+        assert_eq!(first["result"], json!({ "type": "integer", "value": 5 }));
+
+        let (status, second) = post(&server, json!({ "expression": "4 *", "session": first["session"] }));
+
+        assert_eq!(status, 200);
+        assert_eq!(second["result"], json!({ "type": "integer", "value": 20 }));
+        assert_eq!(post(&server, json!({ "expression": "1", "session": "nope" })).0, 404);
+    }
+
+    #[test]
+    fn test_eval_error() {
+        let server = HttpServer::new(HttpConfig::default());
+        let (status, response) = post(&server, json!({ "expression": "1 0 /" }));
+
+        assert_eq!(status, 422);
+        assert_eq!(response["error"]["code"], 4);
+        assert_eq!(response["error"]["column"], 5);
+
+        let (status, response) = post(&server, json!({ "expression": "1 SAVE /tmp/x.json" }));
+
+        assert_eq!(status, 422);
+        assert_eq!(response["error"]["code"], 18);
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut config = HttpConfig::default();
+
+        config.limits.max_steps = Some(2);
+        config.session_ttl = Duration::from_secs(0);
+
+        let server = HttpServer::new(config);
+        let (status, response) = post(&server, json!({ "expression": "1 2 +" }));
+
+        assert_eq!(status, 422);
+        assert_eq!(response["error"]["code"], 16);
+
+        // Sessions expire right away
+        assert_eq!(post(&server, json!({ "expression": "1", "session": response["session"] })).0, 404);
+    }
+
+    #[test]
+    fn test_routes() {
+        let server = HttpServer::new(HttpConfig::default());
+        let (status, body) = server.handle("GET", "/operators", &[]);
+        let operators = serde_json::from_str::<Json>(&body).unwrap();
+
+        assert_eq!(status, 200);
+        assert_eq!(operators["operators"][0]["symbol"], "+");
+        assert_eq!(operators["operators"][0]["effect"], "( x y -- x+y )");
+        assert_eq!(server.handle("GET", "/eval", &[]).0, 405);
+        assert_eq!(server.handle("GET", "/nope", &[]).0, 404);
+        assert_eq!(server.handle("POST", "/eval", b"{}").0, 400);
+    }
+
+    #[test]
+    fn test_connection() {
+        let server = HttpServer::new(HttpConfig { max_body: 64, ..HttpConfig::default() });
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut responses = Vec::new();
+
+            // The large body is refused from its length alone, so it is not even sent
+            for (length, body) in &[(22, r#"{"expression":"6 7 *"}"#), (1000, "")] {
+                let mut stream = TcpStream::connect(address).unwrap();
+                let mut response = String::new();
+
+                write!(stream, "POST /eval HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", length, body).unwrap();
+                stream.read_to_string(&mut response).unwrap();
+                responses.push(response);
+            }
+
+            responses
+        });
+
+        for _ in 0..2 {
+            server.handle_connection(listener.accept().unwrap().0);
+        }
+
+        let responses = client.join().unwrap();
+
+        assert!(responses[0].starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(responses[0].contains(r#""value":42"#));
+        assert!(responses[1].starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    }
+
+    #[test]
+    fn test_slow_client() {
+        let server = HttpServer::new(HttpConfig { request_timeout: Duration::from_millis(200), ..HttpConfig::default() });
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            let mut response = String::new();
+
+            // Each byte comes well within the read timeout, but the whole request never does
+            for byte in b"POST /eval HTTP/1.1\r\nContent-Length: 22\r\n".iter().cycle().take(40) {
+                if stream.write_all(&[*byte]).is_err() {
+                    break;
+                }
+
+                thread::sleep(Duration::from_millis(20));
+            }
+
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+
+        server.handle_connection(listener.accept().unwrap().0);
+
+        assert!(client.join().unwrap().starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+    }
+
+    #[test]
+    fn test_max_connections() {
+        let server = HttpServer::new(HttpConfig { max_connections: 1, ..HttpConfig::default() });
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        thread::spawn(move || server.serve(listener));
+
+        // The first connection is held open, so the second one is turned away
+        let _idle = TcpStream::connect(address).unwrap();
+        let mut stream = TcpStream::connect(address).unwrap();
+        let mut response = String::new();
+
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+    }
+
+    #[test]
+    fn test_connection_slot() {
+        let server = HttpServer::new(HttpConfig { max_connections: 1, ..HttpConfig::default() });
+        let slot = ConnectionSlot::take(&server.connections, 1).unwrap();
+
+        assert!(ConnectionSlot::take(&server.connections, 1).is_none());
+
+        // A worker panicking still gives its slot back
+        let worker = thread::spawn(move || {
+            let _slot = slot;
+
+            panic!("Worker failed");
+        });
+
+        assert!(worker.join().is_err());
+        assert_eq!(server.connections.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_sessions_are_independent() {
+        let server = HttpServer::new(HttpConfig::default());
+        let (_, first) = post(&server, json!({ "expression": "1" }));
+        let (_, second) = post(&server, json!({ "expression": "2" }));
+        let (id, calculator) = server.session(Some(&first["session"])).unwrap();
+        let _busy = calculator.lock().unwrap();
+
+        // A session in use does not hold the others back
+        let (status, third) = post(&server, json!({ "expression": "3 +", "session": second["session"] }));
+
+        assert_eq!(id, first["session"]);
+        assert_eq!(status, 200);
+        assert_eq!(third["result"], json!({ "type": "integer", "value": 5 }));
+    }
+}
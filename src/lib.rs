@@ -0,0 +1,44 @@
+//! Pascaline is a RPN (Reverse Polish Notation) calculator.
+//!
+//! Text is split into tokens by a [`Parser`], and tokens are pushed one by one
+//! to a [`Stack`] which applies operators as they come. A [`Calculator`] glues
+//! both together, along with the [`Registry`] of known operators.
+//!
+//! ```
+//! use pascaline::{ Calculator, Token };
+//!
+//! let mut calculator = Calculator::new();
+//!
+//! calculator.eval("2 3 + 4 *").unwrap();
+//!
+//! assert_eq!(calculator.result(), Some(&Token::new_integer(20)));
+//! ```
+
+mod core;
+mod calculator;
+mod errors;
+// Only exported as C symbols, see include/pascaline.h
+mod ffi;
+#[cfg(feature = "server")]
+mod http;
+#[cfg(feature = "server")]
+mod rpc;
+mod script;
+mod session;
+
+pub use crate::calculator::{ Calculator, Limits };
+pub use crate::core::{
+    Category, Command, Constant, Levels, Metadata, Operator, OperatorImpl, Parser, Registry, Span, Stack, Token,
+    Unit, UnitDef, WordKind, DEFAULT_CAPACITY, MATHEMATICAL, PHYSICAL, UNITS
+};
+pub use crate::core::{ symbols, types };
+pub use crate::errors::{ report, EvalError, PascalineError, ScriptError };
+pub use crate::script::Script;
+
+/// Servers run by the command line, which are not part of the library API
+#[cfg(feature = "server")]
+#[doc(hidden)]
+pub mod servers {
+    pub use crate::http::{ HttpConfig, HttpServer };
+    pub use crate::rpc::{ serve, serve_socket, RpcConfig };
+}
@@ -1,49 +1,625 @@
-mod core;
-mod calculator;
-mod errors;
+mod config;
+mod editor;
+mod tui;
 
-use crate::core::Parser;
-use crate::core::Stack;
-use crate::core::Token;
+use crate::config::Config;
+use crate::editor::EditorHelper;
+use pascaline::{ report, Calculator, Script };
+#[cfg(feature = "server")]
+use pascaline::servers::{ serve, serve_socket, HttpConfig, HttpServer, RpcConfig };
+use ratatui::crossterm::terminal;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::{ Config as EditorConfig, Editor };
+use std::env;
+use std::fs;
+use std::io::{ self, BufRead, IsTerminal, Write };
+#[cfg(feature = "server")]
+use std::net::TcpListener;
+#[cfg(feature = "server")]
+use std::os::unix::net::UnixListener;
+use std::path::{ Path, PathBuf };
+use std::process;
 
-fn main(){
-    let mut stack = Stack::new();
+const USAGE: &str = "\
+Usage : pascaline [-a | --atomic] [--ieee] [--strict] [-r | --result] [--levels <n>] <script.pasc>
+        pascaline [--ieee] [--strict] -e <expression>
+        pascaline [-a | --atomic] [--ieee] [--strict] [-s | --shared] [-]
+        pascaline [-a | --atomic] [--ieee] [--strict] [--fresh] [--levels <n>]
+        pascaline serve [--socket <path>] [--allow-files]
+        pascaline http [--bind <address>]
+        pascaline tui [-a | --atomic] [--ieee] [--strict]
 
-    let t1 = Token::new_integer(2);
-    let t2 = Token::new_integer(3);
-    let t3 = Token::new_operator("+").unwrap();
-    let t4 = Token::new_float(2.5);
-    let t5 = Token::new_operator("-").unwrap();
-    let t6 = Token::new_operator("*").unwrap();
+Without a script, lines are read from the standard input and evaluated one by one.
+Each line is evaluated on its own stack, unless --shared is given.
+From a terminal, a single stack is kept and saved after every successful line,
+then restored at the next start, unless --fresh is given.
+From a terminal, the stack is shown one level per line, level 1 being the top of the stack,
+and only the first 10 levels are shown by default, or as many as --levels gives.
+Defaults are read from $XDG_CONFIG_HOME/pascaline/config.toml, along with a prelude script
+evaluated before anything else (unless a session is restored).
+With --atomic, a failing line (or script) leaves the stack as it was before it.
+With --ieee, invalid operations and overflows give NaN and infinite values instead of errors.
+With --strict, unknown words are errors instead of being ignored.
+With serve, line-delimited JSON-RPC 2.0 requests are answered on the standard input and output,
+or on a Unix domain socket with --socket, each connection getting its own calculator.
+Clients cannot use SAVE and LOAD, unless --allow-files is given.
+With http, a local HTTP API is served (on 127.0.0.1:8080 unless --bind is given).
+With tui, a full-screen interface shows the stack levels and lets them be edited.";
 
-    stack.push(&t1).unwrap();
-    stack.push(&t2).unwrap();
+// Exit codes, so scripts can be used in Makefiles and shell pipelines
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
 
-    println!("{}", stack);
-    println!("\t{} elements", stack.size());
+// Only local clients can reach the HTTP API, unless asked otherwise
+#[cfg(feature = "server")]
+const HTTP_ADDRESS: &str = "127.0.0.1:8080";
 
-    stack.push(&t3).unwrap();
+// Name used to report errors from the standard input
+const STDIN_NAME: &str = "<stdin>";
 
-    println!("{}", stack);
-    println!("\t{} elements", stack.size());
+// Location of the interactive session and of the configuration, under the XDG directories
+const APP_DIR: &str = "pascaline";
+const SESSION_FILE: &str = "session.json";
+const CONFIG_FILE: &str = "config.toml";
+const PROMPT: &str = "> ";
 
-    stack.push(&t4).unwrap();
+#[derive(Debug, Default)]
+struct Options {
+    result_only: bool,
+    shared: bool,
+    // Modes from the configuration, which a restored session replaces
+    atomic: bool,
+    ieee: bool,
+    degrees: bool,
+    // Modes asked on the command line, which win over the restored ones
+    atomic_flag: bool,
+    ieee_flag: bool,
+    strict: bool,
+    fresh: bool,
+    levels: Option<usize>,
+    default_levels: usize,
+    capacity: Option<usize>,
+    history: usize,
+    color: bool,
+    prelude: Option<Script>,
+    aliases: Vec<(String, String)>
+}
+
+impl Options {
+    fn new(config: &Config) -> Options {
+        Options {
+            atomic: config.atomic,
+            ieee: config.ieee,
+            degrees: config.degrees,
+            strict: config.strict,
+            default_levels: config.levels,
+            capacity: config.capacity,
+            history: config.history,
+            color: config.color,
+            aliases: config.aliases.clone(),
+            ..Options::default()
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Mode {
+    Script(String),
+    Expression(String),
+    Filter,
+    Interactive,
+    // Socket to listen on, and whether clients can use SAVE and LOAD
+    #[cfg(feature = "server")]
+    Serve(Option<String>, bool),
+    #[cfg(feature = "server")]
+    Http(String),
+    Tui
+}
+
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(EXIT_USAGE)
+}
+
+fn print_output(calculator: &mut Calculator) {
+    // Messages from commands such as HELP come before the results
+    for message in calculator.take_output() {
+        println!("{}", message);
+    }
+}
+
+fn print_result(calculator: &Calculator) {
+    if let Some(t) = calculator.result() {
+        println!("{}", t);
+    }
+}
+
+fn print_levels(calculator: &Calculator, limit: usize) {
+    // Values are cut to the width of the terminal, when there is one
+    let width = match io::stdout().is_terminal() {
+        true => terminal::size().ok().map(|(w, _)| w as usize),
+        false => None
+    };
+
+    if calculator.stack().size() > 0 {
+        println!("{}", calculator.stack().levels(Some(limit), width));
+    }
+}
+
+fn make_calculator(options: &Options) -> Calculator {
+    let mut calculator = Calculator::new();
+
+    calculator.set_atomic(options.atomic || options.atomic_flag);
+    calculator.set_ieee(options.ieee || options.ieee_flag);
+    calculator.set_degrees(options.degrees);
+    calculator.set_strict(options.strict);
+    calculator.set_capacity(options.capacity);
+
+    // A wrong alias is reported, the other ones still apply
+    for (alias, symbol) in &options.aliases {
+        if let Err(e) = calculator.alias(alias, symbol) {
+            eprintln!("Ignoring the alias '{}' : {}", alias, e);
+        }
+    }
+
+    calculator
+}
+
+fn run_prelude(calculator: &mut Calculator, options: &Options) {
+    // A broken prelude is reported, but does not prevent from using the calculator
+    if let Some(Err(e)) = options.prelude.as_ref().map(|p| p.run(calculator)) {
+        eprintln!("{}", report(&e));
+    }
+
+    print_output(calculator);
+}
+
+fn xdg_path(variable: &str, fallback: &[&str], file: &str) -> Option<PathBuf> {
+    // Follow the XDG base directory specification, falling back on its default location
+    let base = match env::var_os(variable).filter(|d| !d.is_empty()) {
+        Some(d) => PathBuf::from(d),
+        None => fallback.iter().fold(PathBuf::from(env::var_os("HOME")?), |p, d| p.join(d))
+    };
+
+    Some(base.join(APP_DIR).join(file))
+}
+
+fn session_path() -> Option<PathBuf> {
+    xdg_path("XDG_STATE_HOME", &[".local", "state"], SESSION_FILE)
+}
+
+fn set_aside(path: &Path) -> io::Result<PathBuf> {
+    // Previous backups are kept as well, so pick the first free name
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let backup = (0..)
+        .map(|i| path.with_file_name(if i == 0 { format!("{}.bak", name) } else { format!("{}.{}.bak", name, i) }))
+        .find(|b| !b.exists())
+        .unwrap();
+
+    fs::rename(path, &backup).map(|_| backup)
+}
+
+fn load_config() -> Config {
+    let path = match xdg_path("XDG_CONFIG_HOME", &[".config"], CONFIG_FILE) {
+        Some(p) => p,
+        None => return Config::default()
+    };
+
+    match Config::load(&path) {
+        Ok((config, warnings)) => {
+            for warning in warnings {
+                eprintln!("{}: {}", path.display(), warning);
+            }
+
+            config
+        },
+        Err(e) => {
+            eprintln!("{}: {}, using the defaults", path.display(), e);
+            Config::default()
+        }
+    }
+}
+
+fn run_script(path: &Path, options: &Options) -> i32 {
+    let script = match Script::from_file(path) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}", report(&e));
+            return EXIT_FAILURE;
+        }
+    };
+    let mut calculator = make_calculator(options);
+
+    run_prelude(&mut calculator, options);
+
+    let result = script.run(&mut calculator);
+
+    print_output(&mut calculator);
+
+    if let Err(e) = result {
+        eprintln!("{}", report(&e));
+        return EXIT_FAILURE;
+    }
+
+    if options.result_only {
+        print_result(&calculator);
+    } else if let Some(l) = options.levels {
+        print_levels(&calculator, l);
+    } else {
+        println!("{}", calculator.stack());
+    }
+
+    EXIT_SUCCESS
+}
+
+fn run_expression(expression: &str, options: &Options) -> i32 {
+    let mut calculator = make_calculator(options);
+
+    run_prelude(&mut calculator, options);
+
+    let result = calculator.eval(expression);
+
+    print_output(&mut calculator);
+
+    match result {
+        Ok(_) => {
+            print_result(&calculator);
+            EXIT_SUCCESS
+        },
+        Err(e) => {
+            eprintln!("{}", report(&e));
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn run_filter(options: &Options) -> i32 {
+    let stdin = io::stdin();
+    let mut calculator = make_calculator(options);
+    let mut code = EXIT_SUCCESS;
+
+    run_prelude(&mut calculator, options);
+
+    for (index, line) in stdin.lock().lines().enumerate() {
+        let line = match line {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("{}: {}", STDIN_NAME, e);
+                return EXIT_FAILURE;
+            }
+        };
+
+        if !options.shared && index > 0 {
+            calculator = make_calculator(options);
+            run_prelude(&mut calculator, options);
+        }
+
+        // Keep going on errors, like dc does, but remember the failure for the exit code
+        let result = calculator.eval(&line);
+
+        print_output(&mut calculator);
+
+        match result {
+            Ok(_) => print_result(&calculator),
+            Err(e) => {
+                eprintln!("{}:{}:{}: {}", STDIN_NAME, index + 1, e.span.column(&line), report(&e));
+                code = EXIT_FAILURE;
+            }
+        }
+
+        // Flush after each line so results show up right away in pipelines
+        if io::stdout().flush().is_err() {
+            return EXIT_FAILURE;
+        }
+    }
+
+    code
+}
+
+fn run_repl(options: &Options) -> i32 {
+    let mut calculator = make_calculator(options);
+    let mut path = session_path();
+    let levels = options.levels.unwrap_or(options.default_levels);
+
+    // The prelude already ran when the session was first started
+    let restored = match path.clone() {
+        Some(p) if !options.fresh && p.exists() => match calculator.load(&p) {
+            Ok(_) => {
+                // Modes asked on the command line win over the saved ones, unlike the configured defaults
+                if options.atomic_flag {
+                    calculator.set_atomic(true);
+                }
+
+                if options.ieee_flag {
+                    calculator.set_ieee(true);
+                }
+
+                print_levels(&calculator, levels);
+                true
+            },
+            // An unreadable session is moved aside, so saving the fresh one never destroys it
+            Err(e) => {
+                match set_aside(&p) {
+                    Ok(b) => eprintln!("Starting a fresh session, the previous one was moved to '{}' : {}", b.display(), e),
+                    Err(m) => {
+                        eprintln!("Starting a fresh session which is not saved, as the previous one cannot be moved ({}) : {}", m, e);
+                        path = None;
+                    }
+                }
+
+                false
+            }
+        },
+        _ => false
+    };
+
+    if !restored {
+        run_prelude(&mut calculator, options);
+    }
+
+    let editor_config = EditorConfig::builder().max_history_size(options.history).map(|b| b.build());
+    let mut editor = match editor_config.and_then(Editor::<EditorHelper, DefaultHistory>::with_config) {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("{}: {}", STDIN_NAME, e);
+            return EXIT_FAILURE;
+        }
+    };
+
+    // Colors can be turned off, see https://no-color.org
+    let colored = options.color && env::var_os("NO_COLOR").is_none();
+
+    editor.set_helper(Some(EditorHelper::new(calculator.registry().clone(), colored)));
+
+    loop {
+        let line = match editor.readline(PROMPT) {
+            Ok(l) => l,
+            // Ctrl-C drops the current line, like in a shell
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return EXIT_SUCCESS,
+            Err(e) => {
+                eprintln!("{}: {}", STDIN_NAME, e);
+                return EXIT_FAILURE;
+            }
+        };
+
+        if !line.trim().is_empty() {
+            // History only lives as long as the session, so failing to keep a line does not matter
+            let _ = editor.add_history_entry(line.as_str());
+        }
+
+        let result = calculator.eval(&line);
+
+        print_output(&mut calculator);
+
+        match result {
+            Ok(_) => {
+                print_levels(&calculator, levels);
+
+                // The session is only saved once a line went through, so a failure cannot be restored
+                // Saving is atomic, so a crash in the middle never corrupts the previous session
+                if let Some(p) = &path {
+                    let saved = p.parent().map_or(Ok(()), fs::create_dir_all)
+                        .map_err(|e| e.to_string())
+                        .and_then(|_| calculator.save(p).map_err(|e| e.to_string()));
+
+                    if let Err(e) = saved {
+                        eprintln!("Failed to save the session : {}", e);
+                    }
+                }
+            },
+            Err(e) => eprintln!("{}:{}: {}", STDIN_NAME, e.span.column(&line), report(&e))
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+fn run_server(socket: Option<&str>, allow_files: bool) -> i32 {
+    let config = RpcConfig {
+        allow_files,
+        ..RpcConfig::default()
+    };
+    let path = match socket {
+        Some(p) => p,
+        None => {
+            let stdin = io::stdin();
+
+            return match serve(stdin.lock(), io::stdout(), &config) {
+                Ok(_) => EXIT_SUCCESS,
+                Err(e) => {
+                    eprintln!("{}: {}", STDIN_NAME, e);
+                    EXIT_FAILURE
+                }
+            };
+        }
+    };
+    let listener = match UnixListener::bind(path) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Failed to listen on '{}' : {}", path, e);
+            return EXIT_FAILURE;
+        }
+    };
+
+    match serve_socket(listener, config) {
+        Ok(_) => EXIT_SUCCESS,
+        Err(e) => {
+            eprintln!("Failed to accept a connection : {}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+fn run_http(address: &str) -> i32 {
+    let listener = match TcpListener::bind(address) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Failed to listen on '{}' : {}", address, e);
+            return EXIT_FAILURE;
+        }
+    };
+
+    match HttpServer::new(HttpConfig::default()).serve(listener) {
+        Ok(_) => EXIT_SUCCESS,
+        Err(e) => {
+            eprintln!("Failed to accept a connection : {}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn run_tui(options: &Options) -> i32 {
+    let mut calculator = make_calculator(options);
+
+    run_prelude(&mut calculator, options);
+
+    match tui::run(calculator) {
+        Ok(_) => EXIT_SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn main() {
+    let mut args = env::args().skip(1).peekable();
+    let config = load_config();
+    let mut options = Options::new(&config);
+    let mut mode = None;
+
+    // The prelude is read once, then evaluated on every new calculator
+    options.prelude = config.prelude.and_then(|p| match Script::from_file(&p) {
+        Ok(s) => Some(s),
+        Err(e) => {
+            eprintln!("{}", report(&e));
+            None
+        }
+    });
+
+    // Servers are sub-commands with their own options
+    match args.peek().map(String::as_str) {
+        #[cfg(feature = "server")]
+        Some("serve") => {
+            args.next();
+            let mut socket = None;
+            let mut allow_files = false;
+
+            while let Some(arg) = args.next() {
+                match (arg.as_str(), &socket) {
+                    ("--socket", None) => socket = Some(args.next().unwrap_or_else(|| usage_error())),
+                    ("--allow-files", _) => allow_files = true,
+                    _ => usage_error()
+                }
+            }
+
+            mode = Some(Mode::Serve(socket, allow_files));
+        },
+        Some("tui") => {
+            args.next();
+
+            // The other options are about printing results and sessions, which the interface does on its own
+            for arg in args.by_ref() {
+                match arg.as_str() {
+                    "-a" | "--atomic" => options.atomic_flag = true,
+                    "--ieee" => options.ieee_flag = true,
+                    "--strict" => options.strict = true,
+                    _ => usage_error()
+                }
+            }
+
+            mode = Some(Mode::Tui);
+        },
+        #[cfg(feature = "server")]
+        Some("http") => {
+            args.next();
+            mode = match (args.next().as_deref(), args.next(), args.next()) {
+                (None, _, _) => Some(Mode::Http(HTTP_ADDRESS.to_string())),
+                (Some("--bind"), Some(a), None) => Some(Mode::Http(a)),
+                _ => usage_error()
+            };
+        },
+        #[cfg(not(feature = "server"))]
+        Some(s) if (s == "serve") || (s == "http") => {
+            eprintln!("'{}' is not available, as pascaline was built without the server feature", s);
+            process::exit(EXIT_USAGE);
+        },
+        _ => ()
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-r" | "--result" => options.result_only = true,
+            "-s" | "--shared" => options.shared = true,
+            "-a" | "--atomic" => options.atomic_flag = true,
+            "--ieee" => options.ieee_flag = true,
+            "--strict" => options.strict = true,
+            "--fresh" => options.fresh = true,
+            "--levels" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => options.levels = Some(n),
+                None => usage_error()
+            },
+            "-e" | "--eval" => match (args.next(), &mode) {
+                (Some(e), None) => mode = Some(Mode::Expression(e)),
+                _ => usage_error()
+            },
+            "-" if mode.is_none() => mode = Some(Mode::Filter),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            _ if mode.is_none() => mode = Some(Mode::Script(arg)),
+            _ => usage_error()
+        }
+    }
+
+    // Without any input given, the standard input is either a pipeline or a user at a terminal
+    let mode = mode.unwrap_or_else(|| if io::stdin().is_terminal() { Mode::Interactive } else { Mode::Filter });
+    let code = match mode {
+        Mode::Script(p) => run_script(Path::new(&p), &options),
+        Mode::Expression(e) => run_expression(&e, &options),
+        Mode::Filter => run_filter(&options),
+        Mode::Interactive => run_repl(&options),
+        #[cfg(feature = "server")]
+        Mode::Serve(s, f) => run_server(s.as_deref(), f),
+        #[cfg(feature = "server")]
+        Mode::Http(a) => run_http(&a),
+        Mode::Tui => run_tui(&options)
+    };
+
+    process::exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::set_aside;
+    use std::env;
+    use std::fs;
+    use std::process;
 
-    println!("{}", stack);
-    println!("\t{} elements", stack.size());
+    #[test]
+    fn test_set_aside() {
+        let dir = env::temp_dir().join(format!("pascaline-aside-{}", process::id()));
+        let path = dir.join("session.json");
 
-    stack.push(&t5).unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "first").unwrap();
 
-    println!("{}", stack);
-    println!("\t{} elements", stack.size());
+        assert_eq!(set_aside(&path).unwrap(), dir.join("session.json.bak"));
 
-    stack.push(&t1).unwrap();
+        // Earlier backups are never overwritten
+        fs::write(&path, "second").unwrap();
 
-    println!("{}", stack);
-    println!("\t{} elements", stack.size());
+        assert_eq!(set_aside(&path).unwrap(), dir.join("session.json.1.bak"));
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(dir.join("session.json.bak")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(dir.join("session.json.1.bak")).unwrap(), "second");
 
-    stack.push(&t6).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
 
-    println!("{}", stack);
-    println!("\t{} elements", stack.size());
+        assert!(set_aside(&path).is_err());
+    }
 }
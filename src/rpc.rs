@@ -0,0 +1,532 @@
+use crate::calculator::{ Calculator, Limits };
+use crate::core::Stack;
+use crate::errors::{ report, PascalineError };
+use crate::core::Token;
+use crate::session::Value;
+use serde_json::{ json, Value as Json };
+use std::collections::VecDeque;
+use std::io::{ self, BufRead, BufReader, Write };
+use std::os::unix::net::{ UnixListener, UnixStream };
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// Standard JSON-RPC 2.0 error codes
+// Errors raised by the calculator use their own (positive) stable codes instead
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// Error sent to the clients turned away, as JSON-RPC leaves this range to servers
+const SERVER_BUSY: i64 = -32000;
+
+// Number of states kept to undo changes
+const UNDO_DEPTH: usize = 64;
+
+/// Settings of the JSON-RPC server, mostly bounds so a single client cannot hang it
+#[derive(Debug, Clone, Copy)]
+pub struct RpcConfig {
+    /// Bounds applied to each evaluation
+    pub limits: Limits,
+    /// Maximum number of socket connections served at once
+    pub max_connections: usize,
+    /// Whether clients can use `SAVE` and `LOAD`
+    pub allow_files: bool
+}
+
+impl Default for RpcConfig {
+    fn default() -> RpcConfig {
+        RpcConfig {
+            limits: Limits {
+                max_steps: Some(10_000),
+                timeout: Some(Duration::from_millis(100))
+            },
+            max_connections: 64,
+            allow_files: false
+        }
+    }
+}
+
+// Counts a connection being served until it is dropped, even when serving it panics
+pub(crate) struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    // Takes a slot, unless all of them are in use
+    pub fn take(connections: &Arc<AtomicUsize>, max: usize) -> Option<ConnectionSlot> {
+        if connections.fetch_add(1, Ordering::SeqCst) >= max {
+            connections.fetch_sub(1, Ordering::SeqCst);
+            None
+        } else {
+            Some(ConnectionSlot(Arc::clone(connections)))
+        }
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// Error sent back to the client, along with optional details
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Json>
+}
+
+impl RpcError {
+    fn new(code: i64, message: &str) -> RpcError {
+        RpcError {
+            code,
+            message: message.to_string(),
+            data: None
+        }
+    }
+}
+
+impl From<PascalineError> for RpcError {
+    fn from(error: PascalineError) -> RpcError {
+        RpcError {
+            code: i64::from(error.code()),
+            message: error.to_string(),
+            data: None
+        }
+    }
+}
+
+// Values as sent to clients, in the same format as sessions
+fn token_json(token: &Token) -> Option<Json> {
+    Value::from_token(token).map(|v| v.to_json())
+}
+
+// Result and values of a stack, as sent to clients
+pub(crate) fn stack_json(stack: &Stack) -> Json {
+    json!({
+        "result": stack.result().and_then(token_json),
+        "stack": stack.values().iter().filter_map(token_json).collect::<Vec<Json>>()
+    })
+}
+
+/// Calculator driven through line-delimited JSON-RPC 2.0 requests
+///
+/// Each connection owns its own calculator, so clients never see each other's state.
+/// Calculators are bounded and sandboxed by default, so clients cannot hang them nor reach the file system.
+#[derive(Debug)]
+pub(crate) struct Connection {
+    calculator: Calculator,
+    history: VecDeque<Stack>
+}
+
+impl Connection {
+    /// Creates a connection with a fresh calculator, with the default settings
+    pub fn new() -> Connection {
+        Connection::with_config(&RpcConfig::default())
+    }
+
+    /// Creates a connection with a fresh calculator, bounded and sandboxed as configured
+    pub fn with_config(config: &RpcConfig) -> Connection {
+        let mut calculator = Calculator::new();
+
+        calculator.set_limits(config.limits);
+        calculator.set_sandboxed(!config.allow_files);
+
+        Connection {
+            calculator,
+            history: VecDeque::new()
+        }
+    }
+
+    /// Handles a single request, returning the response to send back, if any
+    ///
+    /// Notifications (requests without an id) are run but never answered.
+    pub fn handle(&mut self, line: &str) -> Option<String> {
+        let request = match serde_json::from_str::<Json>(line) {
+            Ok(r) => r,
+            Err(e) => return Some(self.respond(Json::Null, Err(RpcError::new(PARSE_ERROR, &e.to_string()))))
+        };
+        let id = request.get("id").cloned();
+        let method = request.get("method").and_then(Json::as_str);
+        let params = request.get("params").cloned().unwrap_or(Json::Null);
+        let result = match (request.get("jsonrpc").and_then(Json::as_str), method) {
+            (Some("2.0"), Some(m)) => self.call(m, &params),
+            _ => Err(RpcError::new(INVALID_REQUEST, "Not a JSON-RPC 2.0 request"))
+        };
+
+        match (id, method) {
+            (None, Some(_)) => None,
+            (id, _) => Some(self.respond(id.unwrap_or(Json::Null), result))
+        }
+    }
+
+    fn respond(&self, id: Json, result: Result<Json, RpcError>) -> String {
+        let response = match result {
+            Ok(r) => json!({ "jsonrpc": "2.0", "id": id, "result": r }),
+            Err(e) => {
+                let mut error = json!({ "code": e.code, "message": e.message });
+
+                if let Some(data) = e.data {
+                    error["data"] = data;
+                }
+
+                json!({ "jsonrpc": "2.0", "id": id, "error": error })
+            }
+        };
+
+        response.to_string()
+    }
+
+    fn call(&mut self, method: &str, params: &Json) -> Result<Json, RpcError> {
+        match method {
+            "eval" => {
+                let expression = string_param(params, "expression")?;
+
+                // Messages are only sent back for successful evaluations, so drop the ones left by failures
+                self.calculator.take_output();
+                self.record(|c| c.eval(expression).map_err(|e| RpcError {
+                    code: i64::from(e.code()),
+                    message: report(&e),
+                    data: Some(json!({ "token": e.token, "column": e.span.column(expression) }))
+                }))?;
+
+                let mut state = self.state();
+
+                // Messages from commands such as HELP
+                state["output"] = json!(self.calculator.take_output());
+
+                Ok(state)
+            },
+            "push" => {
+                let value = params.get("value").ok_or_else(|| missing("value"))?;
+                let token = Value::from_json(value)
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, &e))?
+                    .into_token()?;
+
+                self.record(|c| c.push(token).map_err(RpcError::from))?;
+
+                Ok(self.state())
+            },
+            "pop" => match self.calculator.result() {
+                None => Err(RpcError::from(PascalineError::EmptyStackError)),
+                Some(_) => {
+                    let token = self.record(|c| Ok(c.pop()))?;
+
+                    Ok(json!({ "value": token.as_ref().and_then(token_json) }))
+                }
+            },
+            "stack" => Ok(self.state()),
+            "undo" => match self.history.pop_back() {
+                Some(stack) => {
+                    self.calculator.set_stack(stack);
+                    Ok(self.state())
+                },
+                None => Err(RpcError::new(INVALID_PARAMS, "Nothing to undo"))
+            },
+            "set_mode" => {
+                if let Some(atomic) = bool_param(params, "atomic")? {
+                    self.calculator.set_atomic(atomic);
+                }
+
+                if let Some(ieee) = bool_param(params, "ieee")? {
+                    self.calculator.set_ieee(ieee);
+                }
+
+                if let Some(degrees) = bool_param(params, "degrees")? {
+                    self.calculator.set_degrees(degrees);
+                }
+
+                Ok(json!({
+                    "atomic": self.calculator.is_atomic(),
+                    "ieee": self.calculator.is_ieee(),
+                    "degrees": self.calculator.is_degrees()
+                }))
+            },
+            "reset" => {
+                self.record(|c| {
+                    c.reset();
+                    Ok(())
+                })?;
+
+                Ok(self.state())
+            },
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, &format!("Unknown method '{}'", method)))
+        }
+    }
+
+    fn record<T, F>(&mut self, f: F) -> Result<T, RpcError>
+    where
+        F: FnOnce(&mut Calculator) -> Result<T, RpcError>
+    {
+        // Keep the state from before the change, so it can be undone
+        // Changes which leave the stack untouched are not worth undoing
+        let snapshot = self.calculator.stack().clone();
+        let result = f(&mut self.calculator);
+        let stack = self.calculator.stack();
+
+        if stack.values() != snapshot.values() || stack.last_op() != snapshot.last_op() {
+            if self.history.len() >= UNDO_DEPTH {
+                self.history.pop_front();
+            }
+
+            self.history.push_back(snapshot);
+        }
+
+        result
+    }
+
+    fn state(&self) -> Json {
+        stack_json(self.calculator.stack())
+    }
+}
+
+impl Default for Connection {
+    fn default() -> Connection {
+        Connection::new()
+    }
+}
+
+fn missing(name: &str) -> RpcError {
+    RpcError::new(INVALID_PARAMS, &format!("Missing parameter '{}'", name))
+}
+
+fn string_param<'a>(params: &'a Json, name: &str) -> Result<&'a str, RpcError> {
+    params.get(name).ok_or_else(|| missing(name))?
+        .as_str().ok_or_else(|| RpcError::new(INVALID_PARAMS, &format!("Parameter '{}' must be a string", name)))
+}
+
+fn bool_param(params: &Json, name: &str) -> Result<Option<bool>, RpcError> {
+    match params.get(name) {
+        None => Ok(None),
+        Some(v) => v.as_bool()
+            .map(Some)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, &format!("Parameter '{}' must be a boolean", name)))
+    }
+}
+
+/// Serves requests read line by line from `input`, writing one response per line to `output`
+pub fn serve<R: BufRead, W: Write>(input: R, mut output: W, config: &RpcConfig) -> io::Result<()> {
+    let mut connection = Connection::with_config(config);
+
+    for line in input.lines() {
+        let line = line?;
+
+        // Blank lines are tolerated, to ease manual testing
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(response) = connection.handle(&line) {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Serves the connections of a Unix domain socket until the listener is closed, each one on its own thread
+pub fn serve_socket(listener: UnixListener, config: RpcConfig) -> io::Result<()> {
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    for stream in listener.incoming() {
+        // A failed connection, or running out of file descriptors for a while, must not stop the server
+        let mut stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to accept a connection : {}", e);
+                continue;
+            }
+        };
+
+        // Busy clients are turned away at once, rather than piling up threads
+        let slot = match ConnectionSlot::take(&connections, config.max_connections) {
+            Some(s) => s,
+            None => {
+                refuse(&mut stream);
+                continue;
+            }
+        };
+
+        thread::spawn(move || {
+            let _slot = slot;
+            let result = stream.try_clone().and_then(|reader| serve(BufReader::new(reader), &stream, &config));
+
+            if let Err(e) = result {
+                eprintln!("Connection closed : {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+fn refuse(stream: &mut UnixStream) {
+    let error = json!({ "jsonrpc": "2.0", "id": null, "error": { "code": SERVER_BUSY, "message": "Too many connections" } });
+
+    // The accept loop never waits on a client : the error is dropped if it cannot be written right away
+    let _ = stream.set_nonblocking(true).and_then(|_| writeln!(stream, "{}", error));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rpc::{ serve, serve_socket, Connection, RpcConfig };
+    use serde_json::{ json, Value as Json };
+    use std::io::{ BufRead, BufReader, Write };
+    use std::os::unix::net::{ UnixListener, UnixStream };
+    use std::thread;
+
+    fn call(connection: &mut Connection, request: Json) -> Json {
+        serde_json::from_str(&connection.handle(&request.to_string()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_eval() {
+        let mut c = Connection::new();
+        let response = call(&mut c, json!({ "jsonrpc": "2.0", "id": 1, "method": "eval", "params": { "expression": "1.5 2 +" } }));
+
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["result"], json!({ "type": "float", "value": "3.5" }));
+        assert_eq!(response["result"]["stack"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_eval_error() {
+        let mut c = Connection::new();
+        let response = call(&mut c, json!({ "jsonrpc": "2.0", "id": 1, "method": "eval", "params": { "expression": "1 0 /" } }));
+
+        assert_eq!(response["error"]["code"], 4);
+        assert_eq!(response["error"]["data"], json!({ "token": "/", "column": 5 }));
+    }
+
+    #[test]
+    fn test_push_pop_undo() {
+        let mut c = Connection::new();
+
+        call(&mut c, json!({ "jsonrpc": "2.0", "id": 1, "method": "push", "params": { "value": { "type": "integer", "value": 2 } } }));
+        call(&mut c, json!({ "jsonrpc": "2.0", "id": 2, "method": "push", "params": { "value": { "type": "bool", "value": true } } }));
+
+        let popped = call(&mut c, json!({ "jsonrpc": "2.0", "id": 3, "method": "pop" }));
+
+        assert_eq!(popped["result"]["value"], json!({ "type": "bool", "value": true }));
+
+        let undone = call(&mut c, json!({ "jsonrpc": "2.0", "id": 4, "method": "undo" }));
+
+        assert_eq!(undone["result"]["stack"].as_array().unwrap().len(), 2);
+
+        call(&mut c, json!({ "jsonrpc": "2.0", "id": 5, "method": "reset" }));
+
+        assert_eq!(c.calculator.stack().size(), 0);
+
+        let empty = call(&mut c, json!({ "jsonrpc": "2.0", "id": 6, "method": "pop" }));
+
+        assert_eq!(empty["error"]["code"], 6);
+    }
+
+    #[test]
+    fn test_output() {
+        let mut c = Connection::new();
+        let failed = call(&mut c, json!({ "jsonrpc": "2.0", "id": 1, "method": "eval", "params": { "expression": "HELP + 1 0 /" } }));
+        let help = call(&mut c, json!({ "jsonrpc": "2.0", "id": 2, "method": "eval", "params": { "expression": "HELP DUP" } }));
+
+        // Messages of the failed evaluation are not sent along with the next one
+        assert_eq!(failed["error"]["code"], 4);
+        assert_eq!(help["result"]["output"].as_array().unwrap().len(), 1);
+        assert!(help["result"]["output"][0].as_str().unwrap().starts_with("DUP : "));
+    }
+
+    #[test]
+    fn test_set_mode() {
+        let mut c = Connection::new();
+        let response = call(&mut c, json!({ "jsonrpc": "2.0", "id": 1, "method": "set_mode", "params": { "ieee": true, "degrees": true } }));
+
+        assert_eq!(response["result"], json!({ "atomic": false, "ieee": true, "degrees": true }));
+        assert!(c.calculator.is_ieee());
+        assert!(c.calculator.is_degrees());
+    }
+
+    #[test]
+    fn test_sandboxed() {
+        let mut c = Connection::new();
+
+        // Clients cannot reach the file system, even after a reset
+        for (id, expression) in [(1, "SAVE /tmp/pascaline.json"), (2, "LOAD /etc/passwd")] {
+            let response = call(&mut c, json!({ "jsonrpc": "2.0", "id": id, "method": "eval", "params": { "expression": expression } }));
+
+            assert_eq!(response["error"]["code"], 18);
+            call(&mut c, json!({ "jsonrpc": "2.0", "id": id, "method": "reset" }));
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut c = Connection::new();
+        let response = call(&mut c, json!({ "jsonrpc": "2.0", "id": 1, "method": "eval", "params": { "expression": "1 DROP ".repeat(5_001) } }));
+
+        // Clients get the same bounds as with the HTTP API
+        assert_eq!(response["error"]["code"], 16);
+        call(&mut c, json!({ "jsonrpc": "2.0", "id": 2, "method": "reset" }));
+
+        // History is bounded, dropping the oldest states
+        for i in 0..100 {
+            call(&mut c, json!({ "jsonrpc": "2.0", "id": i, "method": "push", "params": { "value": { "type": "integer", "value": i } } }));
+        }
+
+        assert_eq!(c.history.len(), 64);
+        assert_eq!(c.history.front().unwrap().size(), 36);
+    }
+
+    #[test]
+    fn test_serve_socket() {
+        let path = std::env::temp_dir().join(format!("pascaline-rpc-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        thread::spawn(move || serve_socket(listener, RpcConfig { max_connections: 1, ..RpcConfig::default() }));
+
+        // The first connection is served, and held open so the second one is turned away
+        let mut first = UnixStream::connect(&path).unwrap();
+        let mut response = String::new();
+
+        writeln!(first, "{}", json!({ "jsonrpc": "2.0", "id": 1, "method": "eval", "params": { "expression": "6 7 *" } })).unwrap();
+        BufReader::new(&first).read_line(&mut response).unwrap();
+
+        assert_eq!(serde_json::from_str::<Json>(&response).unwrap()["result"]["result"]["value"], 42);
+
+        response.clear();
+        BufReader::new(UnixStream::connect(&path).unwrap()).read_line(&mut response).unwrap();
+
+        assert_eq!(serde_json::from_str::<Json>(&response).unwrap()["error"]["code"], -32000);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let mut c = Connection::new();
+        let parse = serde_json::from_str::<Json>(&c.handle("{").unwrap()).unwrap();
+        let method = call(&mut c, json!({ "jsonrpc": "2.0", "id": 1, "method": "nope" }));
+        let params = call(&mut c, json!({ "jsonrpc": "2.0", "id": 2, "method": "eval" }));
+
+        assert_eq!(parse["error"]["code"], -32700);
+        assert_eq!(method["error"]["code"], -32601);
+        assert_eq!(params["error"]["code"], -32602);
+
+        // Notifications are never answered
+        assert!(c.handle(r#"{ "jsonrpc": "2.0", "method": "eval", "params": { "expression": "1" } }"#).is_none());
+        assert_eq!(c.calculator.stack().size(), 1);
+    }
+
+    #[test]
+    fn test_serve() {
+        let input = "{ \"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"eval\", \"params\": { \"expression\": \"2 3 *\" } }\n\n";
+        let mut output = Vec::new();
+
+        serve(input.as_bytes(), &mut output, &RpcConfig::default()).unwrap();
+
+        let response = serde_json::from_slice::<Json>(&output).unwrap();
+
+        assert_eq!(response["result"]["result"], json!({ "type": "integer", "value": 6 }));
+    }
+}
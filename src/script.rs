@@ -0,0 +1,116 @@
+use crate::calculator::Calculator;
+use crate::errors::ScriptError;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+/// A program made of RPN source lines, run through a calculator
+#[derive(Debug)]
+pub struct Script {
+    path: PathBuf,
+    source: String
+}
+
+impl Script {
+    /// Creates a script from its source, `path` only being used to report errors
+    pub fn new(path: &Path, source: String) -> Script {
+        Script {
+            path: path.to_path_buf(),
+            source
+        }
+    }
+
+    /// Reads a script from a file
+    pub fn from_file(path: &Path) -> Result<Script, ScriptError> {
+        match fs::read_to_string(path) {
+            Ok(source) => Ok(Script::new(path, source)),
+            Err(e) => Err(ScriptError::ReadError {
+                path: path.to_path_buf(),
+                source: e
+            })
+        }
+    }
+
+    /// Path of the script, used to report errors
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Runs the script line by line, stopping at the first error
+    pub fn run(&self, calculator: &mut Calculator) -> Result<(), ScriptError> {
+        // In atomic mode, the whole program is applied or nothing is
+        if calculator.is_atomic() {
+            calculator.transaction(|c| self.run_lines(c))
+        } else {
+            self.run_lines(calculator)
+        }
+    }
+
+    fn run_lines(&self, calculator: &mut Calculator) -> Result<(), ScriptError> {
+        for (index, line) in self.source.lines().enumerate() {
+            if let Err(e) = calculator.eval(line) {
+                // Lines are reported starting from 1, like columns
+                return Err(ScriptError::RunError {
+                    path: self.path.clone(),
+                    line: index + 1,
+                    column: e.span.column(line),
+                    source: Box::new(e)
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calculator::Calculator;
+    use crate::core::Token;
+    use crate::errors::{ report, ScriptError };
+    use crate::script::Script;
+    use std::path::Path;
+
+    #[test]
+    fn test_run() {
+        let source = String::from("# Multiply a sum\n2 3 +\n\n4 * # Comment\n");
+        let script = Script::new(Path::new("test.pasc"), source);
+        let mut c = Calculator::new();
+
+        script.run(&mut c).unwrap();
+
+        assert_eq!(c.result(), Some(&Token::new_integer(20)));
+    }
+
+    #[test]
+    fn test_run_error_location() {
+        let source = String::from("1 2 +\n  3 DROP DROP DROP\n4\n");
+        let script = Script::new(Path::new("test.pasc"), source);
+        let mut c = Calculator::new();
+        let error = script.run(&mut c).unwrap_err();
+
+        match error {
+            ScriptError::RunError { line, column, .. } => {
+                assert_eq!(line, 2);
+                assert_eq!(column, 15);
+            },
+            ref e => panic!("Unexpected error : {:?}", e)
+        }
+
+        assert_eq!(report(&error), "test.pasc:2:15: Failed to evaluate 'DROP': Computation stack is empty");
+        assert_eq!(c.stack().size(), 0);
+    }
+
+    #[test]
+    fn test_run_atomic() {
+        let source = String::from("1 2 +\n  3 DROP DROP DROP DROP\n4\n");
+        let script = Script::new(Path::new("test.pasc"), source);
+        let mut c = Calculator::new();
+
+        c.set_atomic(true);
+        c.eval("42").unwrap();
+
+        assert!(script.run(&mut c).is_err());
+        assert_eq!(c.stack().size(), 1);
+        assert_eq!(c.result(), Some(&Token::new_integer(42)));
+    }
+}
@@ -0,0 +1,323 @@
+use crate::core::{ Operator, Registry, Token, Unit };
+use crate::core::types::{ Float, Int };
+use crate::errors::PascalineError;
+use serde_json::{ json, Map, Value as Json };
+use std::convert::TryFrom;
+use std::fs::{ self, File, OpenOptions };
+use std::io::{ self, Write };
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+
+/// Version of the session format, bumped on every incompatible change
+///
+/// Version 2 added quantities. Version 1 files are read as they are, as they only lack them.
+pub(crate) const SESSION_VERSION: u32 = 2;
+
+// Values are tagged with their type so integers and floats are kept apart
+// Floats are written as strings, which round-trip exactly and handle NaN and infinite values
+#[derive(Debug)]
+pub(crate) enum Value {
+    Integer(Int),
+    Float(String),
+    Bool(bool),
+    // Written as the value and its unit, such as "9.81_m/s^2"
+    Quantity(String)
+}
+
+impl Value {
+    pub fn from_token(token: &Token) -> Option<Value> {
+        match *token {
+            Token::Integer(i) => Some(Value::Integer(i)),
+            Token::Float(f) => Some(Value::Float(f.to_string())),
+            Token::Bool(b) => Some(Value::Bool(b)),
+            Token::Quantity(f, u) => Some(Value::Quantity(format!("{}_{}", f, u))),
+            // Operators are applied as soon as they are pushed, so they never end up in the stack
+            _ => None
+        }
+    }
+
+    pub fn into_token(self) -> Result<Token, PascalineError> {
+        match self {
+            Value::Integer(i) => Ok(Token::Integer(i)),
+            Value::Float(f) => match f.parse::<Float>() {
+                Ok(f) => Ok(Token::Float(f)),
+                Err(_) => Err(PascalineError::SessionFormatError(format!("invalid float '{}'", f)))
+            },
+            Value::Bool(b) => Ok(Token::Bool(b)),
+            Value::Quantity(q) => {
+                let quantity = q.split_once('_').and_then(|(f, u)| Some((f.parse::<Float>().ok()?, Unit::parse(u)?)));
+
+                match quantity {
+                    Some((f, u)) => Ok(Token::new_quantity(f, u)),
+                    None => Err(PascalineError::SessionFormatError(format!("invalid quantity '{}'", q)))
+                }
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        let (kind, value) = match self {
+            Value::Integer(i) => ("integer", json!(i)),
+            Value::Float(f) => ("float", json!(f)),
+            Value::Bool(b) => ("bool", json!(b)),
+            Value::Quantity(q) => ("quantity", json!(q))
+        };
+
+        json!({ "type": kind, "value": value })
+    }
+
+    pub fn from_json(json: &Json) -> Result<Value, String> {
+        let value = match (json.get("type").and_then(Json::as_str), json.get("value")) {
+            (Some("integer"), Some(v)) => v.as_i64().and_then(|i| Int::try_from(i).ok()).map(Value::Integer),
+            (Some("float"), Some(v)) => v.as_str().map(|f| Value::Float(f.to_string())),
+            (Some("bool"), Some(v)) => v.as_bool().map(Value::Bool),
+            (Some("quantity"), Some(v)) => v.as_str().map(|q| Value::Quantity(q.to_string())),
+            _ => None
+        };
+
+        value.ok_or_else(|| format!("invalid value {}", json))
+    }
+}
+
+// State of a calculator, as saved in a session
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Session {
+    pub values: Vec<Token>,
+    pub last_op: Option<Operator>,
+    pub atomic: bool,
+    pub ieee: bool,
+    pub degrees: bool
+}
+
+impl Session {
+    pub fn to_json(&self) -> String {
+        let file = json!({
+            "version": SESSION_VERSION,
+            "stack": self.values.iter().filter_map(Value::from_token).map(|v| v.to_json()).collect::<Vec<Json>>(),
+            "last_op": self.last_op.map(|o| o.symbol()),
+            "atomic": self.atomic,
+            "ieee": self.ieee,
+            "degrees": self.degrees
+        });
+
+        // Safe to unwrap, JSON values can always be written
+        serde_json::to_string_pretty(&file).unwrap()
+    }
+
+    pub fn from_json(text: &str, registry: &Registry) -> Result<Session, PascalineError> {
+        let format_error = |e: String| PascalineError::SessionFormatError(e);
+        let json = serde_json::from_str::<Json>(text).map_err(|e| format_error(e.to_string()))?;
+        let file = json.as_object().ok_or_else(|| format_error(String::from("expected an object")))?;
+
+        // Only the version is read first, as the rest of a newer format may be anything
+        let version = field(file, "version", Json::as_u64).and_then(|v| u32::try_from(v).map_err(|e| e.to_string()));
+        let version = version.map_err(format_error)?;
+
+        if version > SESSION_VERSION {
+            return Err(PascalineError::SessionVersionError {
+                found: version,
+                supported: SESSION_VERSION
+            });
+        }
+
+        let stack = field(file, "stack", Json::as_array).map_err(format_error)?;
+        let last_op = field(file, "last_op", |j| if j.is_null() { Some(None) } else { j.as_str().map(Some) })
+            .map_err(format_error)?;
+        let atomic = field(file, "atomic", Json::as_bool).map_err(format_error)?;
+        let ieee = field(file, "ieee", Json::as_bool).map_err(format_error)?;
+        // Sessions saved before the angle mode were in radians
+        let degrees = match file.get("degrees") {
+            Some(_) => field(file, "degrees", Json::as_bool).map_err(format_error)?,
+            None => false
+        };
+        let values = stack.iter()
+            .map(|v| Value::from_json(v).map_err(format_error)?.into_token())
+            .collect::<Result<Vec<Token>, PascalineError>>()?;
+
+        // Custom operators must have been registered before loading
+        let last_op = match last_op {
+            Some(s) => Some(registry.get(s).ok_or_else(|| PascalineError::OperatorSymbolError(s.to_string()))?),
+            None => None
+        };
+
+        Ok(Session {
+            values,
+            last_op,
+            atomic,
+            ieee,
+            degrees
+        })
+    }
+}
+
+// Reads a field of a session, which must be there and of the expected type
+fn field<'a, T>(file: &'a Map<String, Json>, name: &str, read: impl Fn(&'a Json) -> Option<T>) -> Result<T, String> {
+    match file.get(name) {
+        Some(j) => read(j).ok_or_else(|| format!("invalid field '{}' : {}", name, j)),
+        None => Err(format!("missing field '{}'", name))
+    }
+}
+
+// Temporary files are numbered, so threads saving to the same path never share one
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Write to a temporary file first, then rename it over the target,
+// so a crash never leaves a half written session behind
+pub(crate) fn write_atomically(path: &Path, text: &str) -> io::Result<()> {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+
+    name.push(format!(".{}.{}.tmp", process::id(), TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+
+    let temp = path.with_file_name(name);
+    let result = OpenOptions::new().write(true).create_new(true).open(&temp)
+        .and_then(|mut f| f.write_all(text.as_bytes()).and_then(|_| f.sync_all()))
+        .and_then(|_| fs::rename(&temp, path));
+
+    if result.is_err() {
+        // Nothing much to do if the cleanup fails as well
+        let _ = fs::remove_file(&temp);
+    }
+
+    // The rename itself is only durable once the directory is synced
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new(".")
+    };
+
+    result.and_then(|_| File::open(dir)).and_then(|d| d.sync_all())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{ Operator, Registry, Token, Unit };
+    use crate::core::types::Float;
+    use crate::errors::PascalineError;
+    use crate::session::{ write_atomically, Session };
+    use std::env;
+    use std::fs;
+    use std::process;
+    use std::thread;
+
+    #[test]
+    fn test_round_trip() {
+        let session = Session {
+            values: vec![
+                Token::Integer(2),
+                Token::Float(0.1),
+                Token::Float(3.0),
+                Token::Float(Float::NEG_INFINITY),
+                Token::Bool(true),
+                Token::new_quantity(9.81, Unit::parse("m/s^2").unwrap())
+            ],
+            last_op: Some(Operator::Add),
+            atomic: true,
+            ieee: true,
+            degrees: true
+        };
+        let restored = Session::from_json(&session.to_json(), &Registry::new()).unwrap();
+
+        // Types are kept as they are, even for floats without a fractional part
+        assert_eq!(restored, session);
+    }
+
+    #[test]
+    fn test_version_1() {
+        let text = r#"{
+            "version": 1,
+            "stack": [{ "type": "integer", "value": 2 }, { "type": "float", "value": "0.5" }, { "type": "bool", "value": false }],
+            "last_op": "*",
+            "atomic": false,
+            "ieee": true
+        }"#;
+        let session = Session::from_json(text, &Registry::new()).unwrap();
+
+        assert_eq!(session.values, vec![Token::Integer(2), Token::Float(0.5), Token::Bool(false)]);
+        assert_eq!(session.last_op, Some(Operator::Mul));
+        assert!(!session.atomic);
+        assert!(session.ieee);
+        assert!(!session.degrees);
+        assert!(session.to_json().contains(r#""version": 2"#));
+    }
+
+    #[test]
+    fn test_newer_version() {
+        let text = r#"{ "version": 999, "something": "else" }"#;
+
+        match Session::from_json(text, &Registry::new()) {
+            Err(PascalineError::SessionVersionError { found: 999, .. }) => (),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        let texts = vec![
+            "not json",
+            r#"{ "version": 2 }"#,
+            r#"{ "version": 2, "stack": [{ "type": "float", "value": "abc" }], "last_op": null, "atomic": false, "ieee": false }"#
+        ];
+
+        for text in texts {
+            match Session::from_json(text, &Registry::new()) {
+                Err(PascalineError::SessionFormatError(_)) => (),
+                r => panic!("Unexpected result : {:?}", r)
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_last_operator() {
+        let text = r#"{ "version": 2, "stack": [], "last_op": "NOPE", "atomic": false, "ieee": false }"#;
+
+        match Session::from_json(text, &Registry::new()) {
+            Err(PascalineError::OperatorSymbolError(s)) => assert_eq!(s, "NOPE"),
+            r => panic!("Unexpected result : {:?}", r)
+        }
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let dir = env::temp_dir().join(format!("pascaline-atomic-{}", process::id()));
+        let path = dir.join("session.json");
+
+        fs::create_dir_all(&dir).unwrap();
+        write_atomically(&path, "first").unwrap();
+        write_atomically(&path, "second").unwrap();
+
+        // Only the target remains, with the last content
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(write_atomically(&path, "missing directory").is_err());
+    }
+
+    #[test]
+    fn test_write_atomically_threads() {
+        let dir = env::temp_dir().join(format!("pascaline-threads-{}", process::id()));
+        let path = dir.join("session.json");
+
+        fs::create_dir_all(&dir).unwrap();
+
+        // Saves from several threads never mix, and leave no temporary file behind
+        let texts = (0..8).map(|i| i.to_string().repeat(10_000)).collect::<Vec<String>>();
+        let threads = texts.iter().cloned().map(|t| {
+            let path = path.clone();
+
+            thread::spawn(move || for _ in 0..10 {
+                write_atomically(&path, &t).unwrap();
+            })
+        }).collect::<Vec<_>>();
+
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        assert!(texts.contains(&fs::read_to_string(&path).unwrap()));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
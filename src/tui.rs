@@ -0,0 +1,365 @@
+use pascaline::{ report, Calculator, Parser, Token };
+use ratatui::crossterm::event::{ self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers };
+use ratatui::layout::{ Constraint, Layout, Rect };
+use ratatui::style::{ Color, Modifier, Style };
+use ratatui::text::{ Line, Span };
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+use std::io;
+
+const PROMPT: &str = "> ";
+const HINT: &str = "Enter: evaluate  Up: select a level  Ctrl-D: quit";
+const SELECTION_HINT: &str = "Up/Down: move  Enter: edit  C: copy  Del: drop  Esc: back";
+
+/// Full-screen calculator, showing the stack levels like HP calculators do
+///
+/// Levels are counted from the top of the stack : level 1 is the last value, shown at the bottom.
+pub struct App {
+    calculator: Calculator,
+    input: String,
+    // Level being selected, and being edited through the input line
+    selected: Option<usize>,
+    editing: Option<usize>,
+    status: Result<String, String>,
+    quit: bool
+}
+
+impl App {
+    pub fn new(calculator: Calculator) -> App {
+        App {
+            calculator,
+            input: String::new(),
+            selected: None,
+            editing: None,
+            status: Ok(String::from(HINT)),
+            quit: false
+        }
+    }
+
+    fn index(&self, level: usize) -> usize {
+        // Only called with valid levels, between 1 and the size of the stack
+        self.calculator.stack().size() - level
+    }
+
+    fn value(&self, level: usize) -> Token {
+        self.calculator.stack().values()[self.index(level)]
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            if let KeyCode::Char('c') | KeyCode::Char('d') = key.code {
+                self.quit = true;
+            }
+
+            return;
+        }
+
+        match self.selected {
+            Some(level) => self.handle_selection(key.code, level),
+            None => self.handle_input(key.code)
+        }
+    }
+
+    fn handle_input(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            },
+            KeyCode::Enter => self.submit(),
+            KeyCode::Up if self.editing.is_none() && self.calculator.stack().size() > 0 => {
+                self.selected = Some(1);
+                self.status = Ok(String::from(SELECTION_HINT));
+            },
+            KeyCode::Esc => {
+                self.input.clear();
+                self.editing = None;
+                self.status = Ok(String::from(HINT));
+            },
+            _ => ()
+        }
+    }
+
+    fn handle_selection(&mut self, code: KeyCode, level: usize) {
+        let size = self.calculator.stack().size();
+
+        match code {
+            KeyCode::Up if level < size => self.selected = Some(level + 1),
+            KeyCode::Down if level > 1 => self.selected = Some(level - 1),
+            KeyCode::Enter | KeyCode::Char('e') => {
+                self.input = literal(self.value(level));
+                self.editing = Some(level);
+                self.selected = None;
+                self.status = Ok(format!("Editing level {}", level));
+            },
+            KeyCode::Char('c') => {
+                // The value itself is copied, as its display is rounded
+                let value = self.value(level);
+
+                self.selected = None;
+                self.status = self.calculator.stack_mut().push(value)
+                    .map(|_| format!("Copied level {}", level))
+                    .map_err(|e| e.to_string());
+            },
+            KeyCode::Delete | KeyCode::Backspace | KeyCode::Char('d') => {
+                let index = self.index(level);
+
+                self.calculator.stack_mut().remove(index);
+
+                // Keep a level selected, unless the stack is empty
+                self.selected = match self.calculator.stack().size() {
+                    0 => None,
+                    s => Some(level.min(s))
+                };
+                self.status = Ok(format!("Dropped level {}", level));
+            },
+            KeyCode::Esc => {
+                self.selected = None;
+                self.status = Ok(String::from(HINT));
+            },
+            _ => ()
+        }
+    }
+
+    fn submit(&mut self) {
+        let input = std::mem::take(&mut self.input);
+
+        match self.editing.take() {
+            Some(level) => {
+                // An edited level must remain a single value
+                let tokens = Parser.parse(&input, self.calculator.registry());
+
+                self.status = match tokens.as_slice() {
+                    [token] => {
+                        let index = self.index(level);
+
+                        self.calculator.stack_mut().replace(index, *token)
+                            .map(|_| String::from(HINT))
+                            .map_err(|e| e.to_string())
+                    },
+                    _ => Err(format!("Level {} must be a single value", level))
+                };
+
+                if self.status.is_err() {
+                    self.input = input;
+                    self.editing = Some(level);
+                }
+            },
+            None => {
+                let result = self.calculator.eval(&input);
+                let output = self.calculator.take_output();
+
+                self.status = match result {
+                    Ok(_) if output.is_empty() => Ok(String::from(HINT)),
+                    // Only the first line fits in the status line
+                    Ok(_) => Ok(output.join(" ").lines().next().unwrap_or_default().to_string()),
+                    Err(e) => {
+                        // Keep the line so it can be fixed
+                        self.input = input;
+                        Err(report(&e))
+                    }
+                };
+            }
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [modes, levels, input, status] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+            Constraint::Length(1)
+        ]).areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(self.modes()).style(Style::default().add_modifier(Modifier::REVERSED)),
+            modes
+        );
+        frame.render_widget(Paragraph::new(self.levels(levels)), levels);
+        frame.render_widget(Paragraph::new(format!("{}{}", PROMPT, self.input)), input);
+
+        let status_line = match &self.status {
+            Ok(s) => Line::styled(s.as_str(), Style::default().fg(Color::DarkGray)),
+            Err(e) => Line::styled(e.as_str(), Style::default().fg(Color::Red))
+        };
+
+        frame.render_widget(Paragraph::new(status_line), status);
+
+        if self.selected.is_none() {
+            let column = (PROMPT.len() + self.input.chars().count()) as u16;
+
+            frame.set_cursor_position((input.x + column.min(input.width.saturating_sub(1)), input.y));
+        }
+    }
+
+    fn modes(&self) -> String {
+        let flag = |on: bool| if on { "on" } else { "off" };
+
+        format!(
+            " pascaline  atomic: {}  ieee: {}  angle: {}  depth: {}",
+            flag(self.calculator.is_atomic()),
+            flag(self.calculator.is_ieee()),
+            if self.calculator.is_degrees() { "deg" } else { "rad" },
+            self.calculator.stack().size()
+        )
+    }
+
+    fn levels(&self, area: Rect) -> Vec<Line<'static>> {
+        let height = area.height as usize;
+        let size = self.calculator.stack().size();
+        let width = size.max(1).to_string().len();
+        // Scroll up just enough to keep the selected level in sight
+        let offset = self.selected.map_or(0, |l| l.saturating_sub(height));
+
+        (0..height).map(|row| {
+            let level = offset + height - row;
+
+            if level > size {
+                return Line::default();
+            }
+
+            let label = format!("{:>width$}: ", level, width = width);
+            let value = format!("{:>width$}", self.value(level).to_string(), width = (area.width as usize).saturating_sub(label.len()));
+            let style = if self.selected == Some(level) {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+
+            Line::from(vec![Span::styled(label, Style::default().fg(Color::DarkGray)), Span::styled(value, style)])
+        }).collect()
+    }
+}
+
+// Writes a value as it is typed, in full unlike its display, so editing a level does not round it
+fn literal(token: Token) -> String {
+    match token {
+        Token::Float(f) if f.is_finite() => f.to_string(),
+        Token::Quantity(f, u) if f.is_finite() => format!("{}_{}", f, u),
+        t => t.to_string()
+    }
+}
+
+pub fn run(calculator: Calculator) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let mut app = App::new(calculator);
+    let result = loop {
+        if let Err(e) = terminal.draw(|f| app.draw(f)) {
+            break Err(e);
+        }
+
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => app.handle_key(key),
+            Ok(_) => (),
+            Err(e) => break Err(e)
+        }
+
+        if app.quit {
+            break Ok(());
+        }
+    };
+
+    // Always give the terminal back, even on errors
+    ratatui::restore();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tui::App;
+    use pascaline::{ Calculator, Token };
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::{ KeyCode, KeyEvent, KeyModifiers };
+    use ratatui::Terminal;
+
+    fn type_keys(app: &mut App, keys: &[KeyCode]) {
+        for key in keys {
+            app.handle_key(KeyEvent::new(*key, KeyModifiers::NONE));
+        }
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            type_keys(app, &[KeyCode::Char(c)]);
+        }
+
+        type_keys(app, &[KeyCode::Enter]);
+    }
+
+    #[test]
+    fn test_levels() {
+        let mut app = App::new(Calculator::new());
+        let mut terminal = Terminal::new(TestBackend::new(20, 6)).unwrap();
+
+        type_text(&mut app, "1 2.5 TRUE");
+        terminal.draw(|f| app.draw(f)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let lines = (0..6).map(|y| (0..20).map(|x| buffer[(x, y)].symbol()).collect::<String>()).collect::<Vec<String>>();
+
+        // Level 1 is the top of the stack, shown at the bottom
+        assert_eq!(lines[1], "3:                 1");
+        assert_eq!(lines[2], "2:          2.500000");
+        assert_eq!(lines[3], "1:              TRUE");
+        assert!(lines[4].starts_with("> "));
+    }
+
+    #[test]
+    fn test_edit_and_drop() {
+        let mut app = App::new(Calculator::new());
+
+        type_text(&mut app, "1 2 3");
+
+        // Edit level 2, then drop level 3
+        type_keys(&mut app, &[KeyCode::Up, KeyCode::Up, KeyCode::Enter]);
+
+        assert_eq!(app.input, "2");
+
+        type_keys(&mut app, &[KeyCode::Backspace]);
+        type_text(&mut app, "20");
+        type_keys(&mut app, &[KeyCode::Up, KeyCode::Up, KeyCode::Up, KeyCode::Delete]);
+
+        assert_eq!(app.calculator.stack().values(), &[Token::new_integer(20), Token::new_integer(3)]);
+
+        // Copy level 1 onto the stack
+        type_keys(&mut app, &[KeyCode::Down, KeyCode::Char('c')]);
+
+        assert_eq!(app.calculator.stack().size(), 3);
+        assert!(app.selected.is_none());
+    }
+
+    #[test]
+    fn test_full_precision() {
+        let mut app = App::new(Calculator::new());
+
+        type_text(&mut app, "1 3 / 1_km 3 /");
+
+        let values = app.calculator.stack().values().to_vec();
+
+        // Copies are exact, though levels are shown rounded
+        type_keys(&mut app, &[KeyCode::Up, KeyCode::Up, KeyCode::Char('c')]);
+
+        assert_eq!(app.calculator.stack().values(), &[values[0], values[1], values[0]]);
+
+        // Editing a level without changing it keeps its value
+        type_keys(&mut app, &[KeyCode::Up, KeyCode::Up, KeyCode::Enter]);
+
+        assert_eq!(app.input, "0.33333334_km");
+
+        type_keys(&mut app, &[KeyCode::Enter]);
+
+        assert_eq!(app.calculator.stack().values(), &[values[0], values[1], values[0]]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut app = App::new(Calculator::new());
+
+        type_text(&mut app, "1 0 /");
+
+        assert!(app.status.is_err());
+        assert_eq!(app.input, "1 0 /");
+        assert_eq!(app.calculator.stack().size(), 2);
+    }
+}
@@ -0,0 +1,126 @@
+use pascaline::symbols::ADD;
+use pascaline::{
+    report, Calculator, EvalError, Operator, OperatorImpl, PascalineError, Parser, Registry,
+    Script, ScriptError, Span, Stack, Token
+};
+use std::path::Path;
+
+#[derive(Debug)]
+struct Square;
+
+impl OperatorImpl for Square {
+    fn symbol(&self) -> &'static str {
+        "SQ"
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn apply(&self, operands: &[Token]) -> Result<Token, PascalineError> {
+        let x = operands[0].as_float().ok_or(PascalineError::TypeError)?;
+
+        Ok(Token::new_float(x * x))
+    }
+}
+
+static SQUARE: Square = Square;
+
+#[test]
+fn test_calculator() {
+    let mut c = Calculator::new();
+
+    c.eval("2 3 + 4 *").unwrap();
+    c.eval("1.5 # Comment").unwrap();
+
+    assert_eq!(c.stack().values(), &[Token::new_integer(20), Token::new_float(1.5)]);
+    assert_eq!(c.result(), Some(&Token::Float(1.5)));
+    assert_eq!(format!("{}", c.stack()), "[20, 1.500000]");
+}
+
+#[test]
+fn test_calculator_error() {
+    let mut c = Calculator::new();
+    let error = c.eval("1 0 /").unwrap_err();
+
+    assert_eq!(error, EvalError {
+        error: PascalineError::ZeroDivisionError,
+        span: Span { start: 4, end: 5 },
+        token: String::from("/")
+    });
+    assert_eq!(error.code(), 4);
+    assert_eq!(report(&error), "Failed to evaluate '/': Division by zero is impossible");
+}
+
+#[test]
+fn test_custom_operator() {
+    let mut c = Calculator::new();
+
+    c.register(&SQUARE).unwrap();
+    c.eval("3 SQ").unwrap();
+
+    assert_eq!(c.result(), Some(&Token::new_integer(9)));
+    assert_eq!(c.register(&SQUARE), Err(PascalineError::SymbolCollisionError(String::from("SQ"))));
+}
+
+#[test]
+fn test_parser_and_stack() {
+    let parser = Parser;
+    let registry = Registry::new();
+    let mut stack = Stack::new();
+
+    for token in parser.parse("1 2 + 0.5 *", &registry) {
+        stack.push(token).unwrap();
+    }
+
+    assert_eq!(stack.result(), Some(&Token::new_float(1.5)));
+    assert_eq!(Operator::from_symbol(ADD), Ok(Operator::Add));
+    assert_eq!(Token::new_operator(ADD), Ok(Token::Operator(Operator::Add)));
+}
+
+#[test]
+fn test_ieee() {
+    let mut c = Calculator::new();
+
+    c.set_ieee(true);
+    c.eval("-1 SQRT").unwrap();
+
+    assert!(matches!(c.result(), Some(Token::Float(f)) if f.is_nan()));
+
+    c.set_ieee(false);
+
+    assert!(c.eval("1 0 /").is_err());
+    assert_eq!(c.result(), Some(&Token::new_integer(0)));
+}
+
+#[test]
+fn test_script() {
+    let script = Script::new(Path::new("api.pasc"), String::from("1 2 +\nDROP DROP\n"));
+    let mut c = Calculator::new();
+
+    c.set_atomic(true);
+
+    match script.run(&mut c) {
+        Err(ScriptError::RunError { line: 2, column: 6, .. }) => (),
+        r => panic!("Unexpected result : {:?}", r)
+    }
+
+    assert_eq!(c.stack().size(), 0);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_stack() {
+    let mut calculator = Calculator::new();
+
+    calculator.eval("2 3.0 * 1.5 TRUE 9.81_m/s^2").unwrap();
+
+    let text = serde_json::to_string(calculator.stack()).unwrap();
+    let stack = serde_json::from_str::<Stack>(&text).unwrap();
+
+    assert_eq!(stack.values(), calculator.stack().values());
+    assert_eq!(stack.last_op(), calculator.stack().last_op());
+
+    // Stacks only hold values, never operators
+    assert!(serde_json::from_str::<Stack>(r#"{ "values": [{ "type": "operator", "value": "+" }], "last_op": null, "ieee": false }"#).is_err());
+}
@@ -0,0 +1,63 @@
+/* Exercises the C API, compiled and run by tests/ffi.rs */
+
+#include <stdio.h>
+#include <string.h>
+#include "pascaline.h"
+
+#define CHECK(condition) \
+    do { \
+        if (!(condition)) { \
+            fprintf(stderr, "%s:%d: check failed : %s\n", __FILE__, __LINE__, #condition); \
+            return 1; \
+        } \
+    } while (0)
+
+int main(void) {
+    PascalineCalculator *calculator = pascaline_new();
+    PascalineValue value;
+
+    CHECK(calculator != NULL);
+
+    /* Values of every type */
+    CHECK(pascaline_eval(calculator, "2 3 + 4 * 1.5 TRUE") == 0);
+    CHECK(pascaline_error_code(calculator) == 0);
+    CHECK(pascaline_error_message(calculator) == NULL);
+    CHECK(pascaline_depth(calculator) == 3);
+
+    CHECK(pascaline_get(calculator, 0, &value));
+    CHECK(value.kind == PASCALINE_TYPE_INTEGER && value.data.integer == 20);
+    CHECK(pascaline_get(calculator, 1, &value));
+    CHECK(value.kind == PASCALINE_TYPE_FLOAT && value.data.floating == 1.5f);
+    CHECK(pascaline_get(calculator, 2, &value));
+    CHECK(value.kind == PASCALINE_TYPE_BOOL && value.data.boolean);
+    CHECK(!pascaline_get(calculator, 3, &value));
+
+    /* Quantities, read as their magnitude */
+    CHECK(pascaline_eval(calculator, "2.5_km") == 0);
+    CHECK(pascaline_get(calculator, 3, &value));
+    CHECK(value.kind == PASCALINE_TYPE_QUANTITY && value.data.floating == 2.5f);
+    CHECK(!pascaline_get(calculator, 4, &value));
+
+    /* Errors, with their stable code and message */
+    CHECK(pascaline_eval(calculator, "1 0 /") == 4);
+    CHECK(pascaline_error_code(calculator) == 4);
+    CHECK(strcmp(pascaline_error_message(calculator), "Failed to evaluate '/': Division by zero is impossible") == 0);
+    CHECK(pascaline_eval(calculator, "\xff") == 19);
+
+    /* Messages from commands */
+    CHECK(pascaline_eval(calculator, "HELP DUP") == 0);
+    CHECK(strncmp(pascaline_output(calculator), "DUP : ", 6) == 0);
+    CHECK(pascaline_eval(calculator, "1") == 0);
+    CHECK(pascaline_output(calculator) == NULL);
+
+    /* Null pointers are reported, not dereferenced */
+    CHECK(pascaline_eval(NULL, "1") == 22);
+    CHECK(pascaline_depth(NULL) == 0);
+
+    pascaline_free(calculator);
+    pascaline_free(NULL);
+
+    printf("ok\n");
+
+    return 0;
+}
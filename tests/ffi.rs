@@ -0,0 +1,46 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn test_c_api() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // Test binaries live in target/<profile>/deps, next to the C library
+    let exe = env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    let output = deps.join("test_pascaline");
+    let compiled = Command::new("cc")
+        .arg(root.join("tests").join("c").join("test_pascaline.c"))
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg("-L")
+        .arg(deps)
+        .arg("-lpascaline")
+        .arg("-o")
+        .arg(&output)
+        .status();
+
+    // Passing without a C compiler would hide a broken C API
+    let compiled = compiled.expect("A C compiler ('cc') is needed to test the C API");
+
+    assert!(compiled.success(), "Failed to compile the C test program");
+
+    let run = Command::new(&output).env("LD_LIBRARY_PATH", deps).output().unwrap();
+
+    assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+    assert_eq!(String::from_utf8_lossy(&run.stdout), "ok\n");
+}
+
+#[test]
+fn test_header_is_up_to_date() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let generated = PathBuf::from(env!("OUT_DIR")).join("pascaline.h");
+    let generated = fs::read_to_string(&generated).expect("The C header was not generated, see the build warnings");
+    let checked_in = fs::read_to_string(root.join("include").join("pascaline.h")).unwrap();
+
+    assert!(
+        generated == checked_in,
+        "include/pascaline.h is out of date, build with PASCALINE_UPDATE_HEADER=1 to regenerate it"
+    );
+}